@@ -0,0 +1,71 @@
+//! Checks that third-party assets are pinned, downloaded and match their integrity hashes,
+//! before they are bundled with `vendor` feature
+
+use sha2::{Digest, Sha384};
+use std::path::Path;
+
+/// Standard base64 with padding, as in Subresource Integrity hashes
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let group = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * index) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=src/vendor.rs");
+    println!("cargo:rerun-if-changed=vendor");
+    if std::env::var_os("CARGO_FEATURE_VENDOR").is_none() {
+        return;
+    }
+
+    let assets = std::fs::read_to_string("src/vendor.rs").expect("Failed to read src/vendor.rs");
+    let mut problems = Vec::new();
+    for line in assets.lines() {
+        let Some((path, rest)) = line
+            .trim()
+            .strip_prefix("vendored!(\"")
+            .and_then(|line| line.split_once('"'))
+        else {
+            continue;
+        };
+        let Some(pinned) = rest
+            .split_once("Some(\"")
+            .and_then(|(_, rest)| rest.split_once('"'))
+            .map(|(integrity, _)| integrity)
+        else {
+            problems.push(format!("Not pinned: {path}"));
+            continue;
+        };
+        let Ok(content) = std::fs::read(Path::new("vendor").join(path)) else {
+            problems.push(format!("Missing, run vendor/fetch.sh: {path}"));
+            continue;
+        };
+        let integrity = format!("sha384-{}", base64(&Sha384::digest(&content)));
+        if integrity != pinned {
+            problems.push(format!(
+                "Integrity mismatch: {path} is {integrity}, expected {pinned}"
+            ));
+        }
+    }
+    if !problems.is_empty() {
+        panic!(
+            "`vendor` feature requires pinned and verified third-party assets:\n{}",
+            problems.join("\n")
+        );
+    }
+}
@@ -0,0 +1,280 @@
+use crate::highlight::HighlightTheme;
+use crate::theme::Theme;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Name of the config file in project root
+pub const CONFIG_FILE: &str = "oreneo.toml";
+
+/// Site configuration, usually loaded from [CONFIG_FILE]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// Site title
+    pub title: Option<String>,
+    /// Base URL of the site, like "https://example.com/"
+    pub base_url: Option<String>,
+    /// Language of pages, that don't specify it in metadata. "en" by default
+    pub lang: Option<String>,
+    /// Site description, used in feeds
+    pub description: Option<String>,
+    /// Site author, used in feeds
+    pub author: Option<String>,
+    /// Page directory, relative to config file. "page" by default
+    pub source: PathBuf,
+    /// Output directory, relative to config file. "html" by default
+    pub output: PathBuf,
+    /// Settings for every page
+    #[serde(flatten)]
+    pub page: PageConfig,
+    /// Per-directory overrides of page settings. Keys are relative to page directory
+    pub dirs: BTreeMap<PathBuf, PageConfig>,
+    /// RSS and Atom feeds
+    pub feed: FeedConfig,
+    /// `robots.txt`
+    pub robots: RobotsConfig,
+    /// Navigation menu
+    pub nav: NavConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            title: None,
+            base_url: None,
+            lang: None,
+            description: None,
+            author: None,
+            source: PathBuf::from("page"),
+            output: PathBuf::from("html"),
+            page: PageConfig::default(),
+            dirs: BTreeMap::new(),
+            feed: FeedConfig::default(),
+            robots: RobotsConfig::default(),
+            nav: NavConfig::default(),
+        }
+    }
+}
+
+/// Page settings, that can be overriden per directory
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct PageConfig {
+    /// Name of a bundled theme. "default" by default
+    pub theme: Option<String>,
+    /// Code highlighting theme. "github-dark" by default
+    pub highlight_theme: Option<String>,
+    /// Additional assets in `<head>`
+    pub head: HeadConfig,
+    /// Optional features
+    pub extensions: Extensions,
+    /// Levels of nested headings in tables of contents. 3 by default
+    pub toc_depth: Option<usize>,
+}
+
+/// Additional assets in `<head>`. Local paths are relative to project root
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HeadConfig {
+    /// Stylesheets to link
+    pub stylesheets: Vec<String>,
+    /// Scripts to link
+    pub scripts: Vec<String>,
+}
+
+/// Optional features. Unset features use their defaults
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Extensions {
+    /// Code highlighting. Enabled by default
+    pub highlight: Option<bool>,
+    /// Highlight code at build time instead of using highlight.js. Enabled by default,
+    /// if oreneo is built with `syntect` feature
+    pub server_highlight: Option<bool>,
+    /// Write third-party assets into output directory instead of loading them from CDN.
+    /// Requires oreneo to be built with `vendor` feature. Disabled by default
+    pub local_assets: Option<bool>,
+    /// Anchor links (`¶`) next to headings, that show on hover. Disabled by default
+    pub heading_anchors: Option<bool>,
+}
+
+/// RSS and Atom feeds of pages with a date. Feeds require `base-url`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct FeedConfig {
+    /// Generate feeds. Enabled by default
+    pub enabled: Option<bool>,
+    /// Put full page HTML into feeds instead of description. Disabled by default
+    pub full_content: bool,
+    /// Maximum number of entries in a feed. 20 by default
+    pub limit: Option<usize>,
+}
+
+/// `robots.txt` for all user agents. It links `sitemap.xml`, so both require `base-url`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct RobotsConfig {
+    /// Generate `robots.txt`. Enabled by default
+    pub enabled: Option<bool>,
+    /// Paths, that crawlers may visit. Everything is allowed, if both lists are empty
+    pub allow: Vec<String>,
+    /// Paths, that crawlers should not visit
+    pub disallow: Vec<String>,
+}
+
+/// Navigation menu with pages of the site, in layout as `{{nav}}`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct NavConfig {
+    /// Generate navigation menu. Disabled by default
+    pub enabled: bool,
+    /// File in a directory, that lists names of its pages and subdirectories in menu order,
+    /// one per line. "_order.txt" by default
+    pub order_file: Option<String>,
+}
+
+impl Config {
+    /// Load config from a file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        Self::from_source(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parse config from TOML source
+    pub fn from_source(source: &str) -> Result<Self, ConfigError> {
+        let config: Self = toml::from_str(source)?;
+        config.page.validate()?;
+        for page in config.dirs.values() {
+            page.validate()?;
+        }
+        Ok(config)
+    }
+
+    /// Does any page write third-party assets into output directory
+    pub fn local_assets(&self) -> bool {
+        self.page.extensions.local_assets()
+            || self
+                .dirs
+                .values()
+                .any(|page| page.extensions.local_assets == Some(true))
+    }
+
+    /// Are feeds generated: they are enabled and base URL is set
+    pub fn feeds(&self) -> bool {
+        self.base_url.is_some() && self.feed.enabled.unwrap_or(true)
+    }
+
+    /// Page settings for a page, with overrides of all its parent directories applied.
+    /// Path is relative to page directory
+    pub fn page_config(&self, page_path: &Path) -> PageConfig {
+        let mut config = self.page.clone();
+        for (dir, overrides) in &self.dirs {
+            if page_path.starts_with(dir) {
+                config.merge(overrides);
+            }
+        }
+        config
+    }
+}
+
+impl PageConfig {
+    /// Check that selected themes and features are available
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if let Some(theme) = &self.theme {
+            if Theme::find(theme).is_none() {
+                return Err(ConfigError::UnknownTheme(theme.clone()));
+            }
+        }
+        if let Some(theme) = &self.highlight_theme {
+            if HighlightTheme::find(theme).is_none() {
+                return Err(ConfigError::UnknownHighlightTheme(theme.clone()));
+            }
+        }
+        if self.extensions.local_assets() && !crate::vendor::AVAILABLE {
+            return Err(ConfigError::VendorNotAvailable);
+        }
+        Ok(())
+    }
+
+    /// Apply overrides on top of these settings. Head assets are appended
+    pub fn merge(&mut self, overrides: &PageConfig) {
+        if overrides.theme.is_some() {
+            self.theme = overrides.theme.clone();
+        }
+        if overrides.highlight_theme.is_some() {
+            self.highlight_theme = overrides.highlight_theme.clone();
+        }
+        self.head
+            .stylesheets
+            .extend(overrides.head.stylesheets.iter().cloned());
+        self.head
+            .scripts
+            .extend(overrides.head.scripts.iter().cloned());
+        self.extensions.merge(&overrides.extensions);
+        self.toc_depth = overrides.toc_depth.or(self.toc_depth);
+    }
+
+    /// Levels of nested headings in tables of contents
+    pub fn toc_depth(&self) -> usize {
+        self.toc_depth.unwrap_or(3)
+    }
+}
+
+impl Extensions {
+    fn merge(&mut self, overrides: &Extensions) {
+        self.highlight = overrides.highlight.or(self.highlight);
+        self.server_highlight = overrides.server_highlight.or(self.server_highlight);
+        self.local_assets = overrides.local_assets.or(self.local_assets);
+        self.heading_anchors = overrides.heading_anchors.or(self.heading_anchors);
+    }
+
+    /// Is code highlighting enabled
+    pub fn highlight(&self) -> bool {
+        self.highlight.unwrap_or(true)
+    }
+
+    /// Are third-party assets written into output directory
+    pub fn local_assets(&self) -> bool {
+        self.local_assets.unwrap_or(false)
+    }
+
+    /// Are anchor links shown next to headings
+    pub fn heading_anchors(&self) -> bool {
+        self.heading_anchors.unwrap_or(false)
+    }
+
+    /// Is code highlighted at build time
+    pub fn server_highlight(&self) -> bool {
+        self.highlight() && crate::highlight::AVAILABLE && self.server_highlight.unwrap_or(true)
+    }
+}
+
+// * ------------------------------------- Error ------------------------------------ * //
+/// An error occured while loading config
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    /// IO error while reading config
+    #[error("Config load error")]
+    IOError(
+        #[source]
+        #[from]
+        std::io::Error,
+    ),
+    /// Invalid TOML or unexpected fields
+    #[error("Config parse error")]
+    ParseError(
+        #[source]
+        #[from]
+        toml::de::Error,
+    ),
+    /// Theme doesn't exist
+    #[error("Unknown theme: '{0}'")]
+    UnknownTheme(String),
+    /// Highlighting theme doesn't exist
+    #[error("Unknown highlight theme: '{0}'")]
+    UnknownHighlightTheme(String),
+    /// Local assets are requested, but not bundled
+    #[error("Local assets require oreneo to be built with `vendor` feature")]
+    VendorNotAvailable,
+}
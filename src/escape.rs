@@ -0,0 +1,61 @@
+/// Escape text for HTML content: `&`, `<` and `>`
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Escape text for HTML attribute values, and for XML: `&`, `<`, `>` and `"`
+pub fn escape_attr(text: &str) -> String {
+    escape_html(text).replace('"', "&quot;")
+}
+
+/// Decode `%XX` escapes in a URL. Invalid escapes are kept as written
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escape = (bytes[index] == b'%')
+            .then(|| text.get(index + 1..index + 3))
+            .flatten()
+            .filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes() {
+        assert_eq!(
+            escape_html(r#"<a href="x">&amp;</a>"#),
+            r#"&lt;a href="x"&gt;&amp;amp;&lt;/a&gt;"#
+        );
+        assert_eq!(escape_attr(r#""a" & b"#), "&quot;a&quot; &amp; b");
+    }
+
+    #[test]
+    fn percent_decoding() {
+        assert_eq!(percent_decode("a%20b%2Fc%2fd"), "a b/c/d");
+        assert_eq!(percent_decode("%C3%BC%E2%9C%93"), "ü✓");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%2"), "%2");
+        assert_eq!(percent_decode("%zz%+1%%41"), "%zz%+1%A");
+        assert_eq!(percent_decode("ü%"), "ü%");
+        assert_eq!(percent_decode("%%FF"), "%\u{fffd}");
+    }
+}
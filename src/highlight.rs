@@ -0,0 +1,136 @@
+use std::path::{Path, PathBuf};
+
+/// Is build-time highlighting compiled in
+pub const AVAILABLE: bool = cfg!(feature = "syntect");
+
+/// Versioned path of highlight.js script, see [crate::vendor]
+pub const HLJS_SCRIPT: &str = "highlight.js/11.8.0/highlight.min.js";
+
+/// Class of highlighted `<code>` elements. Highlighted tokens use it as a prefix
+pub const CLASS: &str = "hl-code";
+
+/// A highlighting theme, available both in highlight.js and in build-time highlighter
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HighlightTheme {
+    /// Theme name
+    pub name: &'static str,
+    /// highlight.js style, without extension
+    pub hljs: &'static str,
+    /// Syntect theme
+    pub syntect: &'static str,
+}
+
+/// Name of the highlighting theme, used when none is selected
+pub const DEFAULT_THEME: &str = "github-dark";
+
+/// Available highlighting themes
+pub const THEMES: &[HighlightTheme] = &[
+    HighlightTheme {
+        name: "github-dark",
+        hljs: "github-dark",
+        syntect: "base16-ocean.dark",
+    },
+    HighlightTheme {
+        name: "github",
+        hljs: "github",
+        syntect: "InspiredGitHub",
+    },
+    HighlightTheme {
+        name: "ocean",
+        hljs: "base16/ocean",
+        syntect: "base16-ocean.dark",
+    },
+    HighlightTheme {
+        name: "eighties",
+        hljs: "base16/eighties",
+        syntect: "base16-eighties.dark",
+    },
+    HighlightTheme {
+        name: "mocha",
+        hljs: "base16/mocha",
+        syntect: "base16-mocha.dark",
+    },
+    HighlightTheme {
+        name: "solarized-dark",
+        hljs: "base16/solarized-dark",
+        syntect: "Solarized (dark)",
+    },
+    HighlightTheme {
+        name: "solarized-light",
+        hljs: "base16/solarized-light",
+        syntect: "Solarized (light)",
+    },
+];
+
+impl HighlightTheme {
+    /// Find a highlighting theme by name
+    pub fn find(name: &str) -> Option<&'static HighlightTheme> {
+        THEMES.iter().find(|theme| theme.name == name)
+    }
+
+    /// Versioned path of highlight.js stylesheet, see [crate::vendor]
+    pub fn hljs_path(&self) -> String {
+        format!("highlight.js/11.8.0/styles/{}.min.css", self.hljs)
+    }
+
+    /// Path of build-time highlighting stylesheet, relative to output root
+    pub fn stylesheet_path(&self) -> PathBuf {
+        Path::new("highlight").join(format!("{}.css", self.name))
+    }
+
+    /// Build-time highlighting stylesheet. Returns [None] if highlighting is not compiled in
+    pub fn stylesheet(&self) -> Option<String> {
+        #[cfg(feature = "syntect")]
+        return imp::stylesheet(self.syntect);
+        #[cfg(not(feature = "syntect"))]
+        None
+    }
+}
+
+#[cfg(feature = "syntect")]
+mod imp {
+    use std::sync::OnceLock;
+    use syntect::highlighting::ThemeSet;
+    use syntect::html::{ClassStyle, ClassedHTMLGenerator};
+    use syntect::parsing::SyntaxSet;
+    use syntect::util::LinesWithEndings;
+
+    const STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+    fn syntax_set() -> &'static SyntaxSet {
+        static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
+        SYNTAX_SET.get_or_init(SyntaxSet::load_defaults_newlines)
+    }
+
+    fn theme_set() -> &'static ThemeSet {
+        static THEME_SET: OnceLock<ThemeSet> = OnceLock::new();
+        THEME_SET.get_or_init(ThemeSet::load_defaults)
+    }
+
+    pub fn highlight(code: &str, language: &str) -> Option<String> {
+        let syntax_set = syntax_set();
+        let syntax = syntax_set.find_syntax_by_token(language)?;
+        let mut generator = ClassedHTMLGenerator::new_with_class_style(syntax, syntax_set, STYLE);
+        for line in LinesWithEndings::from(code) {
+            generator
+                .parse_html_for_line_which_includes_newline(line)
+                .ok()?;
+        }
+        Some(generator.finalize())
+    }
+
+    pub fn stylesheet(theme: &str) -> Option<String> {
+        let theme = theme_set().themes.get(theme)?;
+        syntect::html::css_for_theme_with_class_style(theme, STYLE).ok()
+    }
+}
+
+/// Highlight code in a language, producing HTML with classed spans.
+/// Returns [None] if the language is unknown or highlighting is not compiled in
+#[allow(unused_variables)]
+pub fn highlight(code: &str, language: &str) -> Option<String> {
+    #[cfg(feature = "syntect")]
+    return imp::highlight(code, language);
+    #[cfg(not(feature = "syntect"))]
+    None
+}
@@ -2,6 +2,27 @@
 //! Neopolitan is Alan's thing, check it out [here](https://www.alanwsmith.com/neopolitan)
 //! This crate helps generating HTML from neopolitan, simplified version of [neopoligin](https://www.alanwsmith.com/neopoligin/)
 
+/// A [regex::Regex], compiled once on first use
+macro_rules! regex {
+    ($pattern: expr) => {{
+        static REGEX: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+        REGEX.get_or_init(|| regex::Regex::new(&$pattern).unwrap())
+    }};
+}
 
+/// Site configuration, oreneo.toml
+pub mod config;
+/// Escaping of text for HTML and XML, and decoding of URLs
+pub mod escape;
+/// Build-time code highlighting. Only does something with `syntect` feature enabled
+pub mod highlight;
 /// Page. Just a single page
 pub mod page;
+/// Site. A directory of pages
+pub mod site;
+/// Layout templates, a tiny template engine
+pub mod template;
+/// Themes, bundled into the binary
+pub mod theme;
+/// Third-party assets, like highlight.js. Bundled into the binary with `vendor` feature
+pub mod vendor;
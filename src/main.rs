@@ -1,65 +1,211 @@
 use anyhow::Context;
-use oreneo::page::Page;
-
-fn parse_dir<RP, OP, PP>(project_root: RP, output_root: OP, path: PP) -> anyhow::Result<()>
-where
-    RP: AsRef<std::path::Path>,
-    OP: AsRef<std::path::Path>,
-    PP: AsRef<std::path::Path>,
-{
-    let project_root = project_root.as_ref();
-    let output_root = output_root.as_ref();
-    let path = path.as_ref();
-    for file in std::fs::read_dir(project_root.join(path))
-        .context("Failed to read page dir")?
-        .flatten()
-    {
-        let file_name = file.file_name();
-        let file_name = file_name.to_string_lossy();
-        let file_name = file_name.as_ref();
-        let page_path = path.join(file_name);
-        if page_path.extension().and_then(|ext| ext.to_str()) == Some("neo") {
-            let page = Page::load(&project_root.join(&page_path))
-                .context(format!("Failed to parse page {:?}!", page_path))?;
-
-            let generated_html = page
-                .to_html_string(
-                    &pathdiff::diff_paths(
-                        ".",
-                        page_path.parent().context("Page has no parent???")?,
-                    )
-                    .context("Failed to construct relative path of project root for page!")?,
-                )
-                .context(format!("Failed to build page {:?}!", page_path))?;
-
-            let html_path = output_root.join(page_path.with_extension("html"));
-            std::fs::write(&html_path, generated_html)
-                .context(format!("Failed to write page {html_path:?}!"))?;
-        } else if project_root.join(&page_path).is_dir() {
-            parse_dir(project_root, output_root, page_path)?;
-        }
-    }
+use oreneo::config::{Config, CONFIG_FILE};
+use oreneo::page::BuildOptions;
+use oreneo::site::{LinkSource, Site};
+use std::path::{Path, PathBuf};
 
-    Ok(())
-}
+/// Development server
+mod serve;
+/// Watch mode
+mod watch;
 
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
 
 /// Neopolitan parser and HTML generator
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
 struct CliArgs {
-    /// Page directory. "page" by default
-    #[arg(default_value = "page")]
-    page_dir: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    build: BuildArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Build the site. Default, if no command is given
+    Build(BuildArgs),
+    /// Build the site, serve it on localhost and reload pages in browser when they change
+    Serve(ServeArgs),
+    /// Build the site and check that local links, image sources and `#fragment`s point to
+    /// existing pages, assets and element ids
+    Check(SiteArgs),
+}
+
+#[derive(Args, Debug)]
+struct SiteArgs {
+    /// Page directory. Taken from config, "page" by default
+    page_dir: Option<PathBuf>,
+
+    /// Output directory. Taken from config, "html" by default
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Site config. "oreneo.toml" by default, if it exists
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+
+    /// Theme for pages, that don't select one in metadata. Overrides site config
+    #[arg(short, long)]
+    theme: Option<String>,
 
-    /// Output directory. "html" by default
-    #[arg(short, long, default_value = "html")]
-    output: String,
+    /// Build pages with `-- status: draft` in metadata
+    #[arg(long)]
+    drafts: bool,
+
+    /// Build pages with a date in the future
+    #[arg(long)]
+    future: bool,
+}
+
+impl SiteArgs {
+    fn site(self) -> anyhow::Result<Site> {
+        let config_path = self
+            .config
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(CONFIG_FILE));
+        let (mut config, config_dir) = if self.config.is_some() || config_path.exists() {
+            (
+                Config::load(&config_path)
+                    .context(format!("Failed to load config {config_path:?}"))?,
+                config_path.parent().unwrap_or(Path::new("")).to_owned(),
+            )
+        } else {
+            (Config::default(), PathBuf::new())
+        };
+
+        if let Some(theme) = self.theme {
+            config.page.theme = Some(theme);
+            config.page.validate()?;
+        }
+
+        let page_dir = self
+            .page_dir
+            .unwrap_or_else(|| config_dir.join(&config.source));
+        let output = self
+            .output
+            .unwrap_or_else(|| config_dir.join(&config.output));
+        Ok(Site::new(page_dir, output)
+            .with_config(config)
+            .with_options(BuildOptions {
+                drafts: self.drafts,
+                future: self.future,
+                ..Default::default()
+            }))
+    }
+}
+
+#[derive(Args, Debug)]
+struct BuildArgs {
+    #[command(flatten)]
+    site: SiteArgs,
+
+    /// Watch page directory and rebuild pages when they change
+    #[arg(short, long)]
+    watch: bool,
+
+    /// Rebuild every page, even if it didn't change since the last build
+    #[arg(short, long)]
+    force: bool,
+
+    /// Print time it took to build each page
+    #[arg(long)]
+    timings: bool,
+
+    /// Number of threads to build pages with. Number of CPUs by default
+    #[arg(short, long)]
+    jobs: Option<usize>,
+}
+
+#[derive(Args, Debug)]
+struct ServeArgs {
+    #[command(flatten)]
+    site: SiteArgs,
+
+    /// Address to serve on
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
+
+    /// Port to serve on
+    #[arg(short, long, default_value_t = 3000)]
+    port: u16,
+}
+
+fn build(args: BuildArgs) -> anyhow::Result<()> {
+    if let Some(jobs) = args.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()?;
+    }
+
+    let site = args.site.site()?;
+    if args.force {
+        site.clear_cache()?;
+    }
+    if args.watch {
+        watch::watch(&site, || ())
+    } else {
+        let report = site.build()?;
+        if args.timings {
+            for (page_path, time) in &report.built {
+                println!("{:>8.2}ms {page_path:?}", time.as_secs_f64() * 1000.0);
+            }
+        }
+        print!(
+            "Built {} pages, {} up to date",
+            report.built.len(),
+            report.skipped.len()
+        );
+        if !report.excluded.is_empty() {
+            print!(", {} drafts or scheduled", report.excluded.len());
+        }
+        println!();
+        Ok(())
+    }
+}
+
+fn serve(args: ServeArgs) -> anyhow::Result<()> {
+    let site = args.site.site()?;
+    let options = BuildOptions {
+        dev: true,
+        ..site.options().clone()
+    };
+    let site = site.with_options(options);
+    serve::serve(&site, &format!("{}:{}", args.host, args.port))
+}
+
+fn check(args: SiteArgs) -> anyhow::Result<()> {
+    let site = args.site()?;
+    site.build()?;
+    let broken = site.check()?;
+    for link in &broken {
+        match (&link.source, link.line) {
+            (LinkSource::Page(page_path), Some(line)) => {
+                print!("{}:{line}: ", site.page_root().join(page_path).display())
+            }
+            (LinkSource::Page(page_path), None) => {
+                print!("{} (layout): ", site.page_root().join(page_path).display())
+            }
+            (LinkSource::Generated(path), _) => {
+                print!("{}: ", site.output_root().join(path).display())
+            }
+        }
+        println!("broken link '{}', {}", link.link, link.target);
+    }
+    if !broken.is_empty() {
+        anyhow::bail!("Found {} broken links", broken.len());
+    }
+    println!("No broken links");
+    Ok(())
 }
 
 fn main() -> anyhow::Result<()> {
     let args = CliArgs::parse();
-    parse_dir(&args.page_dir, &args.output, ".")?;
-    Ok(())
+    match args.command {
+        Some(Command::Build(args)) => build(args),
+        Some(Command::Serve(args)) => serve(args),
+        Some(Command::Check(args)) => check(args),
+        None => build(args.build),
+    }
 }
@@ -1,4 +1,6 @@
+use super::metadata::parse_quoted;
 use super::PageParseError;
+use crate::escape::escape_attr;
 
 /// An attribute
 #[derive(Clone, Debug, PartialEq)]
@@ -36,17 +38,140 @@ pub enum Attribute {
     Source(String),
     /// -- url: https://example.com/quote_source_url
     Url(String),
+    /// -- depth: 3, works for toc
+    Depth(String),
+}
+
+/// Names of known attributes
+const NAMES: &[&str] = &[
+    "alt", "class", "hidden", "id", "show", "src", "title", "subtitle", "by", "source", "url",
+    "depth",
+];
+
+/// Split an attribute into name and raw value at the first colon,
+/// so values can contain colons: `-- alt: Ratio 16:9`
+pub(super) fn split_attr(attr: &str) -> (&str, Option<&str>) {
+    match attr.split_once(':') {
+        Some((name, value)) => (name.trim(), Some(value)),
+        None => (attr.trim(), None),
+    }
+}
+
+/// Parse an attribute value. Plain values are trimmed, quoted values (`"  value "`)
+/// are kept as-is and support escapes: `\"`, `\\`, `\n`, `\t`.
+/// Returns [None] for empty values
+pub(super) fn parse_attr_value(name: &str, value: &str) -> Result<Option<String>, PageParseError> {
+    let value = value.trim();
+    if !value.starts_with('"') {
+        return Ok(Some(value.to_owned()).filter(|value| !value.is_empty()));
+    }
+    let wrong_value = |err| PageParseError::WrongAttributeValue(name.to_owned(), err);
+    let (value, rest) = parse_quoted(value).map_err(wrong_value)?;
+    if !rest.trim().is_empty() {
+        return Err(wrong_value(format!(
+            "Unexpected '{}' after quoted value",
+            rest.trim()
+        )));
+    }
+    Ok(Some(value))
+}
+
+/// Split on a separator, that is not inside a quoted value
+fn split_unquoted(source: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (index, c) in source.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => {
+                parts.push(&source[start..index]);
+                start = index + c.len_utf8();
+            }
+            _ => (),
+        }
+    }
+    parts.push(&source[start..]);
+    parts
+}
+
+/// Can a character be a part of an attribute or metadata name: letters, digits, `-` and `_`
+pub(super) fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_'
+}
+
+/// Inline attributes, separated by `|`: `name: value`, `name: "quoted | value"`, `name`
+/// and `#id.class` shorthand. Classes are merged into one `class` attribute.
+/// Invalid attributes are skipped
+pub(super) fn parse_inline_attrs(attrs: &str) -> Vec<(String, Option<String>)> {
+    let mut parsed = Vec::new();
+    let mut classes = Vec::new();
+    let mut class_index = None;
+    for attr in split_unquoted(attrs, '|') {
+        let attr = attr.trim();
+        if attr.starts_with(['#', '.']) {
+            for token in attr.split_whitespace() {
+                let mut rest = token;
+                while let Some(kind) = rest.chars().next().filter(|c| matches!(c, '#' | '.')) {
+                    rest = &rest[1..];
+                    let end = rest.find(['#', '.']).unwrap_or(rest.len());
+                    let name = &rest[..end];
+                    rest = &rest[end..];
+                    if name.is_empty() {
+                        continue;
+                    }
+                    if kind == '#' {
+                        parsed.push((String::from("id"), Some(name.to_owned())));
+                    } else {
+                        class_index.get_or_insert(parsed.len());
+                        classes.push(name.to_owned());
+                    }
+                }
+            }
+            continue;
+        }
+
+        let (name, value) = split_attr(attr);
+        if name.is_empty() || !name.chars().all(is_name_char) {
+            continue;
+        }
+        let value = match value.map(|value| parse_attr_value(name, value)) {
+            Some(Ok(Some(value))) => Some(value),
+            Some(_) => continue,
+            None => None,
+        };
+        if name == "class" {
+            class_index.get_or_insert(parsed.len());
+            classes.extend(
+                value
+                    .iter()
+                    .flat_map(|value| value.split_whitespace().map(str::to_owned)),
+            );
+        } else {
+            parsed.push((name.to_owned(), value));
+        }
+    }
+    if let Some(index) = class_index {
+        parsed.insert(index, (String::from("class"), Some(classes.join(" "))));
+    }
+    parsed
 }
 
 impl Attribute {
+    /// Is it a known attribute, like `id: charlie` or `hidden`
+    pub(super) fn is_attribute(attr: &str) -> bool {
+        NAMES.contains(&split_attr(attr).0)
+    }
+
     pub(super) fn parse(attr: &str) -> Result<Option<Attribute>, PageParseError> {
-        let mut attr_name = String::new();
-        let mut attr_value = String::new();
-        let attr_value = if scanf::sscanf!(attr, "{}: {}", attr_name, attr_value).is_ok() {
-            Some(attr_value)
-        } else {
-            attr_name = attr.to_owned();
-            None
+        let (attr_name, attr_value) = split_attr(attr);
+        let attr_name = attr_name.to_owned();
+        let attr_value = match attr_value {
+            Some(value) => parse_attr_value(&attr_name, value)?,
+            None => None,
         };
 
         macro_rules! with_arg {
@@ -79,23 +204,78 @@ impl Attribute {
             "by" => with_arg!(Attribute::By),
             "source" => with_arg!(Attribute::Source),
             "url" => with_arg!(Attribute::Url),
+            "depth" => with_arg!(Attribute::Depth),
             _ => Ok(None),
         }
     }
 
     pub(super) fn to_html(&self) -> Option<String> {
         match self {
-            Attribute::Alt(alt) => Some(format!("alt=\"{alt}\"")),
-            Attribute::Class(class) => Some(format!("class=\"{class}\"")),
+            Attribute::Alt(alt) => Some(format!("alt=\"{}\"", escape_attr(alt))),
+            Attribute::Class(class) => Some(format!("class=\"{}\"", escape_attr(class))),
             Attribute::Hidden => Some(String::from("hidden")),
-            Attribute::Id(id) => Some(format!("id=\"{id}\"")),
+            Attribute::Id(id) => Some(format!("id=\"{}\"", escape_attr(id))),
             Attribute::Show => None,
-            Attribute::Src(src) => Some(format!("src=\"{src}\"")),
-            Attribute::Title(title) => Some(format!("title=\"{title}\"")),
+            Attribute::Src(src) => Some(format!("src=\"{}\"", escape_attr(src))),
+            Attribute::Title(title) => Some(format!("title=\"{}\"", escape_attr(title))),
             Attribute::Subtitle(_) => None,
             Attribute::By(_) => None,
             Attribute::Source(_) => None,
             Attribute::Url(_) => None,
+            Attribute::Depth(_) => None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attrs(source: &str) -> Vec<(String, Option<String>)> {
+        parse_inline_attrs(source)
+    }
+
+    fn attr(name: &str, value: Option<&str>) -> (String, Option<String>) {
+        (name.to_owned(), value.map(str::to_owned))
+    }
+
+    #[test]
+    fn plain_and_quoted_values() {
+        assert_eq!(
+            attrs(r#"data-x: 1| title: "a | b" |hidden"#),
+            vec![
+                attr("data-x", Some("1")),
+                attr("title", Some("a | b")),
+                attr("hidden", None)
+            ]
+        );
+        assert_eq!(
+            attrs(r#"title: "say \"hi\" \\""#),
+            vec![attr("title", Some(r#"say "hi" \"#))]
+        );
+    }
+
+    #[test]
+    fn shorthand_and_classes() {
+        assert_eq!(
+            attrs("#main.alfa.bravo|class: charlie delta|.echo"),
+            vec![
+                attr("id", Some("main")),
+                attr("class", Some("alfa bravo charlie delta echo"))
+            ]
+        );
+        assert_eq!(attrs("#.|."), Vec::new());
+    }
+
+    #[test]
+    fn invalid_attributes_are_skipped() {
+        assert_eq!(
+            attrs(r#"title: "unterminated|bad name: x|: y|lang: en"#),
+            Vec::new()
+        );
+        assert_eq!(
+            attrs(r#"title: "a" b|bad name: x|: y|lang: en"#),
+            vec![attr("lang", Some("en"))]
+        );
+    }
+}
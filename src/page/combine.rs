@@ -0,0 +1,169 @@
+use super::outline::Heading;
+use super::section::Section;
+use super::{BuildOptions, Metadata, Page, PageBuildError};
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+/// Resolve `..` and `.` in a relative path. [None], if it leaves the directory it's relative to
+pub(crate) fn normalize_path(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => normalized.push(name),
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return None;
+                }
+            }
+            Component::CurDir | Component::RootDir => (),
+            Component::Prefix(_) => return None,
+        }
+    }
+    Some(normalized)
+}
+
+/// Is it a link to another site or a non-file URL, like `mailto:`
+pub(crate) fn is_external_link(link: &str) -> bool {
+    link.starts_with("//")
+        || link
+            .split_once(':')
+            .is_some_and(|(scheme, _)| !scheme.contains(['/', '?', '#']))
+}
+
+/// Rewrite values of `id`, `href` and `src` attributes in tags of HTML.
+/// `rewrite` gets attribute name and value
+pub(crate) fn rewrite_attributes(html: &str, rewrite: impl Fn(&str, &str) -> String) -> String {
+    let tag = regex!(r"<[a-zA-Z][^>]*>");
+    let attribute = regex!(r#"(\s(id|href|src)\s*=\s*")([^"]*)""#);
+    tag.replace_all(html, |captures: &regex::Captures| {
+        attribute
+            .replace_all(&captures[0], |captures: &regex::Captures| {
+                format!("{}{}\"", &captures[1], rewrite(&captures[2], &captures[3]))
+            })
+            .into_owned()
+    })
+    .into_owned()
+}
+
+/// A chapter of a combined page
+struct Chapter {
+    /// Path of chapter HTML, relative to output root
+    html_path: PathBuf,
+    /// Prefix of ids in the chapter, also the id of the chapter itself
+    prefix: String,
+}
+
+impl Chapter {
+    fn id(&self, fragment: &str) -> String {
+        if fragment.is_empty() {
+            self.prefix.clone()
+        } else {
+            format!("{}-{fragment}", self.prefix)
+        }
+    }
+
+    /// Heading of the chapter with its subheadings, as they are in the combined page
+    fn heading(&self, heading: &Heading) -> Heading {
+        Heading {
+            level: heading.level,
+            id: self.id(&heading.id),
+            text: heading.text.clone(),
+            children: heading
+                .children
+                .iter()
+                .map(|child| self.heading(child))
+                .collect(),
+        }
+    }
+}
+
+/// Rewrite ids, `#fragment`s and relative links in tags of chapter HTML,
+/// so that it works as a part of the combined page
+fn rebase_chapter(html: &str, chapter: &Chapter, chapters: &[Chapter], page_dir: &Path) -> String {
+    let chapter_dir = chapter.html_path.parent().unwrap_or(Path::new(""));
+    rewrite_attributes(html, |name, value| {
+        if name == "id" {
+            return chapter.id(value);
+        }
+        if value.is_empty() || value.starts_with('/') || is_external_link(value) {
+            return value.to_owned();
+        }
+        let (path, fragment) = value.split_once('#').unwrap_or((value, ""));
+        if path.is_empty() {
+            return format!("#{}", chapter.id(fragment));
+        }
+        let Some(target) = normalize_path(&chapter_dir.join(path)) else {
+            return value.to_owned();
+        };
+        if name == "href" {
+            if let Some(other) = chapters.iter().find(|other| other.html_path == target) {
+                return format!("#{}", other.id(fragment));
+            }
+        }
+        let Some(rebased) = pathdiff::diff_paths(&target, page_dir) else {
+            return value.to_owned();
+        };
+        let rebased = rebased.to_string_lossy();
+        match value.split_once('#') {
+            Some(_) => format!("{rebased}#{fragment}"),
+            None => rebased.into_owned(),
+        }
+    })
+}
+
+impl Page {
+    /// A page with several pages as its chapters, like a printable version of a book.
+    /// Chapters are given with their path, relative to page directory, as is the path
+    /// of the combined page, and with project root, relative to chapter directory.
+    /// Every chapter is rendered with its own project root, its relative links are rebased
+    /// onto the combined page, and its ids and `#fragment`s get a prefix from chapter path,
+    /// like `guide-start-setup`. Links to other chapters point inside the combined page
+    pub fn combine(
+        chapters: &[(PathBuf, PathBuf, Page)],
+        metadata: Metadata,
+        page_path: &Path,
+        options: &BuildOptions,
+    ) -> Result<Self, PageBuildError> {
+        let mut prefixes = HashSet::new();
+        let mut counts = HashMap::new();
+        let rebased = chapters
+            .iter()
+            .map(|(chapter_path, _, _)| {
+                let mut slug = super::slugify(&chapter_path.with_extension("").to_string_lossy());
+                if slug.is_empty() {
+                    slug = String::from("chapter");
+                }
+                let mut prefix = slug.clone();
+                while !prefixes.insert(prefix.clone()) {
+                    let count = counts.entry(slug.clone()).or_insert(0);
+                    *count += 1;
+                    prefix = format!("{slug}-{count}");
+                }
+                Chapter {
+                    html_path: chapter_path.with_extension("html"),
+                    prefix,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let page_dir = page_path.parent().unwrap_or(Path::new(""));
+        let mut sections = Vec::new();
+        let mut outline = Vec::new();
+        for ((_, project_root, page), chapter) in chapters.iter().zip(&rebased) {
+            let html = page.body_to_html(project_root, options)?;
+            sections.push(Section::Html {
+                content: format!(
+                    "<section class=\"chapter\" id=\"{}\">{}</section>",
+                    chapter.prefix,
+                    rebase_chapter(&html, chapter, &rebased, page_dir)
+                ),
+            });
+            outline.extend(page.outline.iter().map(|heading| chapter.heading(heading)));
+        }
+        Ok(Self {
+            sections,
+            metadata,
+            outline,
+        })
+    }
+}
@@ -0,0 +1,265 @@
+use super::attribute::Attribute;
+use super::section::Section;
+use std::collections::{HashMap, HashSet};
+
+/// A footnote, collected at the end of a page or a container
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Footnote {
+    /// Number of the footnote, in order of first reference on the page
+    pub number: usize,
+    /// Footnote is `fn-<anchor>`, its first reference is `fnref-<anchor>`
+    pub anchor: String,
+    /// Footnote text
+    pub content: String,
+}
+
+/// Is it a footnote definition: `-- footnote` with `-- id`
+fn definition(section: &Section) -> Option<(&str, &str)> {
+    match section {
+        Section::Text {
+            class: Some(class),
+            attributes,
+            content,
+            ..
+        } if class.iter().any(|class| class == "footnote") => {
+            attributes.iter().find_map(|attr| match attr {
+                Attribute::Id(id) => Some((id.as_str(), content.as_str())),
+                _ => None,
+            })
+        }
+        _ => None,
+    }
+}
+
+struct Collector {
+    /// Footnote definitions: id and text
+    definitions: HashMap<String, String>,
+    /// Referenced definitions: id, number, anchor and number of references
+    referenced: HashMap<String, (usize, String, usize)>,
+    /// Anchors, used by footnotes and their references
+    anchors: HashSet<String>,
+    count: usize,
+}
+
+impl Collector {
+    /// Anchor, that is not used yet. Taken anchors get a numeric suffix
+    fn unique_anchor(&mut self, anchor: String) -> String {
+        let mut unique = anchor.clone();
+        let mut suffix = 1;
+        while !self.anchors.insert(unique.clone()) {
+            suffix += 1;
+            unique = format!("{anchor}-{suffix}");
+        }
+        unique
+    }
+
+    /// Replace footnote references in a text with `<<fnref|number|anchor>>`. Footnotes,
+    /// that are referenced for the first time, are added to notes
+    fn rewrite(&mut self, text: &mut String, notes: &mut Vec<Footnote>) {
+        if !text.contains("^[") && !text.contains("<<fn") {
+            return;
+        }
+        *text = regex!(r"\\[\\^]|\^\[([^\]]*)\]|<<fn\s*\|\s*([^|>]+?)\s*>>")
+            .replace_all(text, |captures: &regex::Captures| {
+                if let Some(content) = captures.get(1) {
+                    self.count += 1;
+                    let anchor = self.unique_anchor(self.count.to_string());
+                    notes.push(Footnote {
+                        number: self.count,
+                        anchor: anchor.clone(),
+                        content: content.as_str().to_owned(),
+                    });
+                    return format!("<<fnref|{}|{anchor}>>", self.count);
+                }
+
+                let Some(id) = captures.get(2).map(|id| id.as_str()) else {
+                    // Escaped `\\` or `\^`
+                    return captures[0].to_owned();
+                };
+                if let Some((number, anchor, references)) = self.referenced.get_mut(id) {
+                    *references += 1;
+                    let reference = format!("<<fnref|{number}|{anchor}:{references}>>");
+                    self.anchors.insert(format!("{anchor}-{references}"));
+                    return reference;
+                }
+                let Some(content) = self.definitions.get(id).cloned() else {
+                    return format!("\\<\\<fn|{id}\\>\\>");
+                };
+                self.count += 1;
+                let mut anchor = super::slugify(id);
+                if anchor.is_empty() {
+                    anchor = self.count.to_string();
+                }
+                let anchor = self.unique_anchor(anchor);
+                notes.push(Footnote {
+                    number: self.count,
+                    anchor: anchor.clone(),
+                    content,
+                });
+                self.referenced
+                    .insert(id.to_owned(), (self.count, anchor.clone(), 1));
+                format!("<<fnref|{}|{anchor}>>", self.count)
+            })
+            .into_owned();
+    }
+
+    /// Rewrite references in sections. Footnotes, that are first referenced inside
+    /// a container, are collected at its end. Returns the rest of footnotes
+    fn collect(&mut self, sections: &mut [Section]) -> Vec<Footnote> {
+        let mut notes = Vec::new();
+        for section in sections.iter_mut() {
+            if definition(section).is_some() {
+                continue;
+            }
+            match section {
+                Section::Container { content, .. } => {
+                    let inner = self.collect(content);
+                    if !inner.is_empty() {
+                        content.push(Section::Footnotes { notes: inner });
+                    }
+                }
+                Section::Text { content, .. }
+                | Section::TextWrapper { content, .. }
+                | Section::Bookmark { content, .. } => self.rewrite(content, &mut notes),
+                Section::Notes { content, .. } | Section::List { content, .. } => {
+                    for item in content {
+                        self.rewrite(item, &mut notes);
+                    }
+                }
+                Section::Checklist {
+                    prelude, content, ..
+                } => {
+                    self.rewrite(prelude, &mut notes);
+                    for item in content {
+                        self.rewrite(item, &mut notes);
+                    }
+                }
+                _ => (),
+            }
+        }
+        notes
+    }
+}
+
+/// Remove definitions, that were moved into collected footnotes
+fn remove_definitions(sections: &mut Vec<Section>, ids: &HashSet<String>) {
+    sections.retain(|section| definition(section).is_none_or(|(id, _)| !ids.contains(id)));
+    for section in sections {
+        if let Section::Container { content, .. } = section {
+            remove_definitions(content, ids);
+        }
+    }
+}
+
+/// Number footnotes in order of first reference and collect them at the end of the page
+/// or the container, where they are first referenced. References are `^[inline text]` and
+/// `<<fn|id>>` for `-- footnote` sections with `-- id`, `\^[` is not a reference.
+/// Unreferenced definitions stay in place
+pub(super) fn collect_footnotes(sections: &mut Vec<Section>) {
+    let mut definitions = HashMap::new();
+    for section in sections.iter_mut() {
+        section.visit_mut(&mut |section| {
+            if let Some((id, content)) = definition(section) {
+                definitions
+                    .entry(id.to_owned())
+                    .or_insert_with(|| content.to_owned());
+            }
+        });
+    }
+
+    let mut collector = Collector {
+        definitions,
+        referenced: HashMap::new(),
+        anchors: HashSet::new(),
+        count: 0,
+    };
+    let notes = collector.collect(sections);
+    remove_definitions(sections, &collector.referenced.into_keys().collect());
+    if !notes.is_empty() {
+        sections.push(Section::Footnotes { notes });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Page;
+    use super::*;
+
+    /// Texts and collected footnotes of a page, in order
+    fn collect(source: &str) -> (Vec<String>, Vec<Vec<Footnote>>) {
+        let mut page = Page::from_source(source).unwrap();
+        let mut texts = Vec::new();
+        let mut footnotes = Vec::new();
+        for section in &mut page.sections {
+            section.visit_mut(&mut |section| match section {
+                Section::Text { content, .. } => texts.push(content.clone()),
+                Section::Footnotes { notes } => footnotes.push(notes.clone()),
+                _ => (),
+            });
+        }
+        (texts, footnotes)
+    }
+
+    fn note(number: usize, anchor: &str, content: &str) -> Footnote {
+        Footnote {
+            number,
+            anchor: anchor.to_owned(),
+            content: content.to_owned(),
+        }
+    }
+
+    #[test]
+    fn inline_and_named() {
+        let (texts, footnotes) = collect(
+            "-- p\nA^[first] <<fn|named>> \\^[not a note]\n\n\
+             -- footnote\n-- id: named\nNamed note\n",
+        );
+        assert_eq!(
+            texts,
+            vec!["A<<fnref|1|1>> <<fnref|2|named>> \\^[not a note]"]
+        );
+        assert_eq!(
+            footnotes,
+            vec![vec![note(1, "1", "first"), note(2, "named", "Named note")]]
+        );
+    }
+
+    #[test]
+    fn repeated_references() {
+        let (texts, footnotes) =
+            collect("-- p\n<<fn|x>> <<fn|x>> <<fn|x>>\n\n-- footnote\n-- id: x\nX\n");
+        assert_eq!(texts, vec!["<<fnref|1|x>> <<fnref|1|x:2>> <<fnref|1|x:3>>"]);
+        assert_eq!(footnotes, vec![vec![note(1, "x", "X")]]);
+    }
+
+    #[test]
+    fn duplicate_definitions_and_anchors() {
+        let (texts, footnotes) = collect(
+            "-- p\n<<fn|1>>^[inline]\n\n\
+             -- footnote\n-- id: 1\nFirst\n\n-- footnote\n-- id: 1\nSecond\n",
+        );
+        assert_eq!(texts, vec!["<<fnref|1|1>><<fnref|2|2>>"]);
+        assert_eq!(
+            footnotes,
+            vec![vec![note(1, "1", "First"), note(2, "2", "inline")]]
+        );
+    }
+
+    #[test]
+    fn missing_and_unreferenced_definitions() {
+        let (texts, footnotes) =
+            collect("-- p\nSee <<fn|missing>>\n\n-- footnote\n-- id: unused\nUnused\n");
+        assert_eq!(texts, vec!["See \\<\\<fn|missing\\>\\>", "Unused"]);
+        assert!(footnotes.is_empty());
+    }
+
+    #[test]
+    fn notes_in_containers() {
+        let (_, footnotes) =
+            collect("-- p\nOut^[outer]\n\n-- div/\n\n-- p\nIn^[inner]\n\n-- /div\n");
+        assert_eq!(
+            footnotes,
+            vec![vec![note(2, "2", "inner")], vec![note(1, "1", "outer")]]
+        );
+    }
+}
@@ -0,0 +1,400 @@
+use super::{BuildOptions, PageParseError};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// A date in page metadata
+pub type Date = DateTime<FixedOffset>;
+
+/// Parse a date. Accepts RFC 3339 (`2023-09-01T12:00:00+03:00`), `2023-09-01 12:00[:00]`
+/// and `2023-09-01`. Dates without offset are in UTC
+pub fn parse_date(value: &str) -> Option<Date> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date);
+    }
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .map(|date| date.and_utc().fixed_offset())
+}
+
+// * ------------------------------------- Value ------------------------------------ * //
+/// A typed metadata value
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// -- name: Some text, or "Quoted text"
+    String(String),
+    /// -- draft: true
+    Bool(bool),
+    /// -- weight: 10
+    Integer(i64),
+    /// -- ratio: 1.5
+    Float(f64),
+    /// -- date: 2023-09-01
+    Date(Date),
+    /// -- tags: [alfa, "bravo, charlie"]
+    List(Vec<Value>),
+}
+
+/// A value in brackets, that is not a list
+enum ListError {
+    /// Unterminated list or a broken quoted item. Such values are errors
+    Broken(String),
+    /// Text after the closing bracket, like `[WIP] 1.50`. Such values are strings
+    NotList,
+}
+
+impl Value {
+    /// Parse a value. Grammar:
+    /// - `"text"` is a string. Escapes: `\"`, `\\`, `\n`, `\t`
+    /// - `[a, b, "c"]` is a list of values, if the whole value is a list.
+    ///   Unterminated lists are errors
+    /// - `true`/`false`, numbers and dates (see [parse_date]) are typed
+    /// - anything else is a string, trimmed, colons included
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let source = source.trim();
+        if source.starts_with('[') {
+            match Self::parse_list(source) {
+                Ok(list) => Ok(list),
+                Err(ListError::Broken(err)) => Err(err),
+                Err(ListError::NotList) => Ok(Value::String(source.to_owned())),
+            }
+        } else if source.starts_with('"') {
+            parse_text(source).map(Value::String)
+        } else {
+            Ok(Self::scalar(source))
+        }
+    }
+
+    fn parse_list(source: &str) -> Result<Self, ListError> {
+        let unterminated = || ListError::Broken(format!("Unterminated list '{source}'"));
+        let Some(mut rest) = source.strip_prefix('[') else {
+            return Err(ListError::NotList);
+        };
+        let mut list = Vec::new();
+        loop {
+            rest = rest.trim_start();
+            if let Some(tail) = rest.strip_prefix(']') {
+                rest = tail;
+                break;
+            }
+            let item;
+            (item, rest) = if rest.starts_with('"') {
+                let (string, rest) = parse_quoted(rest).map_err(ListError::Broken)?;
+                (Value::String(string), rest)
+            } else {
+                let end = rest.find([',', ']']).ok_or_else(unterminated)?;
+                (Self::scalar(rest[..end].trim()), &rest[end..])
+            };
+            list.push(item);
+            rest = rest.trim_start();
+            if let Some(tail) = rest.strip_prefix(',') {
+                rest = tail;
+            } else if rest.is_empty() {
+                return Err(unterminated());
+            } else if !rest.starts_with(']') {
+                return Err(ListError::NotList);
+            }
+        }
+        if !rest.trim().is_empty() {
+            return Err(ListError::NotList);
+        }
+        Ok(Value::List(list))
+    }
+
+    fn scalar(source: &str) -> Self {
+        match source {
+            "true" => return Value::Bool(true),
+            "false" => return Value::Bool(false),
+            _ => (),
+        }
+        if let Ok(integer) = source.parse() {
+            return Value::Integer(integer);
+        }
+        let numeric = source.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+');
+        if let (true, Ok(float)) = (numeric, source.parse()) {
+            return Value::Float(float);
+        }
+        if let Some(date) = parse_date(source) {
+            return Value::Date(date);
+        }
+        Value::String(source.to_owned())
+    }
+
+    /// String value
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    /// Boolean value
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Date value
+    pub fn as_date(&self) -> Option<&Date> {
+        match self {
+            Value::Date(date) => Some(date),
+            _ => None,
+        }
+    }
+
+    /// Items of a list value. Other values are a list of one item
+    pub fn as_list(&self) -> &[Value] {
+        match self {
+            Value::List(list) => list,
+            value => std::slice::from_ref(value),
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::String(string) => write!(f, "{string}"),
+            Value::Bool(value) => write!(f, "{value}"),
+            Value::Integer(value) => write!(f, "{value}"),
+            Value::Float(value) => write!(f, "{value}"),
+            Value::Date(date) => write!(f, "{}", date.to_rfc3339()),
+            Value::List(list) => {
+                for (index, item) in list.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Parse a text value: a quoted string (see [Value::parse]), or the trimmed source as is
+pub fn parse_text(source: &str) -> Result<String, String> {
+    let source = source.trim();
+    if !source.starts_with('"') {
+        return Ok(source.to_owned());
+    }
+    let (string, rest) = parse_quoted(source)?;
+    if !rest.trim().is_empty() {
+        return Err(format!("Unexpected '{}' after quoted string", rest.trim()));
+    }
+    Ok(string)
+}
+
+/// Parse a quoted string at the start of the source. Returns the string and the rest of the source
+pub(super) fn parse_quoted(source: &str) -> Result<(String, &str), String> {
+    let mut string = String::new();
+    let mut chars = source.char_indices().skip(1);
+    while let Some((_, c)) = chars.next() {
+        match c {
+            '"' => {
+                let end = chars.next().map_or(source.len(), |(index, _)| index);
+                return Ok((string, &source[end..]));
+            }
+            '\\' => match chars.next() {
+                Some((_, '"')) => string.push('"'),
+                Some((_, '\\')) => string.push('\\'),
+                Some((_, 'n')) => string.push('\n'),
+                Some((_, 't')) => string.push('\t'),
+                Some((_, c)) => return Err(format!("Unknown escape sequence '\\{c}'")),
+                None => break,
+            },
+            c => string.push(c),
+        }
+    }
+    Err(format!("Unterminated string {source}"))
+}
+
+// * ----------------------------------- Metadata ----------------------------------- * //
+/// Page metadata, from `-- metadata` sections
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Metadata {
+    /// -- title: Page title. Falls back to the first `-- title` section
+    pub title: Option<String>,
+    /// -- date: 2023-09-01
+    pub date: Option<Date>,
+    /// -- updated: 2023-09-02
+    pub updated: Option<Date>,
+    /// -- author: Name
+    pub author: Option<String>,
+    /// -- description: Short summary of the page
+    pub description: Option<String>,
+    /// -- tags: [alfa, bravo], or comma-separated
+    pub tags: Vec<String>,
+    /// -- lang: en
+    pub lang: Option<String>,
+    /// Other fields, like `theme`, as written. See [Metadata::get] and [Metadata::text]
+    pub extra: BTreeMap<String, String>,
+}
+
+impl Metadata {
+    /// Add fields from a `-- metadata` section. Fields, that are already set, are overriden
+    pub(super) fn extend(&mut self, data: &HashMap<String, String>) -> Result<(), PageParseError> {
+        for (name, source) in data {
+            let error = |err| PageParseError::WrongMetadataFormat(format!("{name}: {err}"));
+            let text = || parse_text(source).map(Some).map_err(error);
+            let date = || match Value::parse(source).map_err(error)? {
+                Value::Date(date) => Ok(Some(date)),
+                value => Err(error(format!("Invalid date '{value}'"))),
+            };
+            match name.as_str() {
+                "title" => self.title = text()?,
+                "date" => self.date = date()?,
+                "updated" => self.updated = date()?,
+                "author" => self.author = text()?,
+                "description" => self.description = text()?,
+                "tags" => {
+                    self.tags = match Value::parse(source).map_err(error)? {
+                        Value::String(tags) => tags
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|tag| !tag.is_empty())
+                            .map(str::to_owned)
+                            .collect(),
+                        value => value.as_list().iter().map(Value::to_string).collect(),
+                    }
+                }
+                "lang" => self.lang = text()?,
+                _ => {
+                    self.extra.insert(name.clone(), source.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply fields, that are set in overrides, on top of these
+    pub fn merge(&mut self, overrides: &Metadata) {
+        macro_rules! merge {
+            ($($field: ident),*) => {
+                $(if overrides.$field.is_some() {
+                    self.$field = overrides.$field.clone();
+                })*
+            };
+        }
+        merge!(title, date, updated, author, description, lang);
+        if !overrides.tags.is_empty() {
+            self.tags = overrides.tags.clone();
+        }
+        self.extra.extend(
+            overrides
+                .extra
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone())),
+        );
+    }
+
+    /// Is the page a draft: `-- status: draft`
+    pub fn is_draft(&self) -> bool {
+        self.text("status").as_deref() == Some("draft")
+    }
+
+    /// Should the page be hidden from search engines: `-- noindex: true`
+    pub fn is_noindex(&self) -> bool {
+        self.get("noindex").and_then(|value| value.as_bool()) == Some(true)
+    }
+
+    /// Is the page scheduled: its date is in the future
+    pub fn is_scheduled(&self) -> bool {
+        self.date.is_some_and(|date| date > chrono::Utc::now())
+    }
+
+    /// Should the page be built: it's not a draft or scheduled, unless options allow it
+    pub fn is_published(&self, options: &BuildOptions) -> bool {
+        (options.drafts || !self.is_draft()) && (options.future || !self.is_scheduled())
+    }
+
+    /// Typed value of a field, that has no typed counterpart
+    pub fn get(&self, name: &str) -> Option<Value> {
+        self.extra
+            .get(name)
+            .and_then(|source| Value::parse(source).ok())
+    }
+
+    /// Text of a field, that has no typed counterpart. Quoted text is unquoted,
+    /// anything else is kept as written
+    pub fn text(&self, name: &str) -> Option<String> {
+        self.extra
+            .get(name)
+            .and_then(|source| parse_text(source).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(text: &str) -> Value {
+        Value::String(text.to_owned())
+    }
+
+    #[test]
+    fn parse_scalars() {
+        assert_eq!(Value::parse(" true "), Ok(Value::Bool(true)));
+        assert_eq!(Value::parse("-10"), Ok(Value::Integer(-10)));
+        assert_eq!(Value::parse("1.5"), Ok(Value::Float(1.5)));
+        assert_eq!(Value::parse("Ratio 16:9"), Ok(string("Ratio 16:9")));
+        assert_eq!(Value::parse("1.2.3"), Ok(string("1.2.3")));
+        assert_eq!(
+            Value::parse("2023-09-01"),
+            Ok(Value::Date(parse_date("2023-09-01T00:00:00Z").unwrap()))
+        );
+    }
+
+    #[test]
+    fn parse_quoted_values() {
+        assert_eq!(
+            Value::parse(r#""  say \"hi\" \\ \n""#),
+            Ok(string("  say \"hi\" \\ \n"))
+        );
+        assert_eq!(Value::parse(r#""true""#), Ok(string("true")));
+        assert!(Value::parse(r#""unterminated"#).is_err());
+        assert!(Value::parse(r#""ends with escape\"#).is_err());
+        assert!(Value::parse(r#""unknown \q escape""#).is_err());
+        assert!(Value::parse(r#""text" after"#).is_err());
+    }
+
+    #[test]
+    fn parse_lists() {
+        assert_eq!(
+            Value::parse(r#"[alfa, "bravo, charlie", 3, ]"#),
+            Ok(Value::List(vec![
+                string("alfa"),
+                string("bravo, charlie"),
+                Value::Integer(3)
+            ]))
+        );
+        assert_eq!(Value::parse("[]"), Ok(Value::List(Vec::new())));
+        assert_eq!(Value::parse("[WIP] 1.50"), Ok(string("[WIP] 1.50")));
+        assert_eq!(
+            Value::parse("[a, b"),
+            Err(String::from("Unterminated list '[a, b'"))
+        );
+        assert!(Value::parse("[a,").is_err());
+        assert!(Value::parse(r#"[a, "b]"#).is_err());
+    }
+
+    #[test]
+    fn quoted_rest() {
+        assert_eq!(
+            parse_quoted(r#""a" rest"#),
+            Ok((String::from("a"), " rest"))
+        );
+        assert_eq!(parse_quoted(r#""""#), Ok((String::new(), "")));
+        assert_eq!(parse_quoted(r#""tab\t""#), Ok((String::from("tab\t"), "")));
+        assert_eq!(parse_quoted(r#""ü\"ö"|"#), Ok((String::from("ü\"ö"), "|")));
+        assert!(parse_quoted(r#""open"#).is_err());
+    }
+}
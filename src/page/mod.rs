@@ -1,16 +1,36 @@
-use build_html::Html;
-use build_html::HtmlContainer;
-use build_html::HtmlPage;
+use crate::config::PageConfig;
+use crate::escape::escape_attr;
+use crate::highlight::HighlightTheme;
+use crate::theme::{Theme, DEFAULT_THEME};
+use crate::vendor;
+use build_html::{HtmlContainer, HtmlPage};
 use section::Section;
-use std::path::Path;
+use itertools::Itertools;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use thiserror::Error;
 
 /// Different attributes, like --hide or --id
 pub mod attribute;
+/// Pages, combined into one, like a printable book
+pub(crate) mod combine;
+/// Footnotes, collected from references in page text
+pub mod footnotes;
+/// Page metadata, like title, date and tags
+pub mod metadata;
+/// Site navigation, a tree of pages
+pub mod nav;
+/// Page outline, a tree of headings
+pub mod outline;
 /// A section, like --title or --html
 pub mod section;
 
 use self::attribute::Attribute;
+use self::metadata::{Date, Metadata, Value};
+use self::nav::NavItem;
+use self::outline::Heading;
+use crate::template::Context;
+use std::collections::{HashMap, HashSet};
 
 fn has_section_prefix(line: &str) -> bool {
     line.starts_with("--") || line.starts_with("```") || line.starts_with('#')
@@ -28,18 +48,201 @@ fn strip_section_prefix(line: &str) -> Option<&str> {
         .map(|line| line.trim())
 }
 
-fn has_attr_prefix(line: &str) -> bool {
-    line.starts_with("--")
-}
-
 fn strip_attr_prefix(line: &str) -> Option<&str> {
     line.strip_prefix("--").map(|line| line.trim())
 }
 
+/// RSS feed of the site in output root
+pub const FEED_FILE: &str = "feed.xml";
+
+/// Atom feed of the site in output root
+pub const ATOM_FILE: &str = "atom.xml";
+
+/// Directory of generated category pages in output, see [category_path]
+pub const CATEGORIES_DIR: &str = "categories";
+
+/// Make a URL-friendly slug out of text: "Hello, World!" becomes "hello-world"
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_owned()
+}
+
+/// Normalized category: slugs of its parts, separated with `/`, like "rust/async".
+/// Empty if the category has no parts
+pub fn category_slug(category: &str) -> String {
+    category
+        .split('/')
+        .map(slugify)
+        .filter(|part| !part.is_empty())
+        .join("/")
+}
+
+/// Path of a category page, relative to output root: `categories/<slug>/index.html`,
+/// next to category feeds. `categories/index.html` lists all categories
+pub fn category_path(category: &str) -> PathBuf {
+    Path::new(CATEGORIES_DIR)
+        .join(category_slug(category))
+        .join("index.html")
+}
+
+/// Categories with a slug, without duplicates by slug. The first of duplicates is kept
+fn unique_categories(categories: impl Iterator<Item = String>) -> Vec<String> {
+    let mut unique: Vec<String> = Vec::new();
+    for category in categories {
+        let slug = category_slug(&category);
+        if !slug.is_empty() && !unique.iter().any(|other| category_slug(other) == slug) {
+            unique.push(category);
+        }
+    }
+    unique
+}
+
+/// Path, that live reload script listens on for reload events
+pub const LIVE_RELOAD_PATH: &str = "/__oreneo/reload";
+
+/// Options for building HTML out of a page
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BuildOptions {
+    /// Development mode. Injects a script, that reloads the page when server sends
+    /// an event to [LIVE_RELOAD_PATH]
+    pub dev: bool,
+    /// Build pages with `-- status: draft`
+    pub drafts: bool,
+    /// Build pages with a date in the future
+    pub future: bool,
+    /// Page settings from site config
+    pub config: PageConfig,
+    /// Site title
+    pub site_title: Option<String>,
+    /// Base URL of the site, used for canonical links
+    pub base_url: Option<String>,
+    /// Language of pages, that don't specify it in metadata. "en" by default
+    pub lang: Option<String>,
+    /// Path of generated HTML file, relative to output root
+    pub path: Option<PathBuf>,
+    /// Site has RSS and Atom feeds, that are linked in `<head>`
+    pub feeds: bool,
+    /// Site navigation. Empty, if disabled
+    pub nav: Arc<Vec<NavItem>>,
+    /// Previous page in book mode: title and path of HTML file, relative to output root
+    pub prev: Option<(String, PathBuf)>,
+    /// Next page in book mode: title and path of HTML file, relative to output root
+    pub next: Option<(String, PathBuf)>,
+}
+
+impl BuildOptions {
+    /// Absolute URL of the page, if base URL and path are known
+    pub fn url(&self) -> Option<String> {
+        let base_url = self.base_url.as_ref()?;
+        let path = self.path.as_ref()?;
+        Some(format!(
+            "{}/{}",
+            base_url.trim_end_matches('/'),
+            path.components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .join("/")
+        ))
+    }
+}
+
+/// Link to an asset. Local paths are relative to project root
+fn asset_link(project_root: &Path, link: &str) -> String {
+    if link.contains("://") || link.starts_with("//") {
+        link.to_owned()
+    } else {
+        project_root
+            .join(link.trim_start_matches('/'))
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+/// Contents of `<head>`
+#[derive(Clone, Debug, Default)]
+struct Head(String);
+
+impl Head {
+    fn stylesheet(&mut self, href: &str) {
+        self.0
+            .push_str(&format!("<link href=\"{href}\" rel=\"stylesheet\">"));
+    }
+
+    fn script(&mut self, src: &str) {
+        self.0.push_str(&format!("<script src=\"{src}\"></script>"));
+    }
+
+    /// Stylesheet from [crate::vendor]
+    fn vendored_stylesheet(&mut self, (href, integrity): (String, Option<String>)) {
+        match integrity {
+            Some(integrity) => self.0.push_str(&format!(
+                "<link href=\"{href}\" rel=\"stylesheet\" integrity=\"{integrity}\" crossorigin=\"anonymous\">"
+            )),
+            None => self.stylesheet(&href),
+        }
+    }
+
+    /// Script from [crate::vendor]
+    fn vendored_script(&mut self, (src, integrity): (String, Option<String>)) {
+        match integrity {
+            Some(integrity) => self.0.push_str(&format!(
+                "<script src=\"{src}\" integrity=\"{integrity}\" crossorigin=\"anonymous\"></script>"
+            )),
+            None => self.script(&src),
+        }
+    }
+
+    fn script_literal(&mut self, code: &str) {
+        self.0.push_str(&format!("<script>{code}</script>"));
+    }
+
+    fn title(&mut self, title: &str) {
+        self.0
+            .push_str(&format!("<title>{}</title>", escape_attr(title)));
+    }
+
+    fn meta(&mut self, name: &str, content: &str) {
+        self.0.push_str(&format!(
+            "<meta name=\"{name}\" content=\"{}\">",
+            escape_attr(content)
+        ));
+    }
+
+    fn property(&mut self, property: &str, content: &str) {
+        self.0.push_str(&format!(
+            "<meta property=\"{property}\" content=\"{}\">",
+            escape_attr(content)
+        ));
+    }
+
+    fn link(&mut self, rel: &str, href: &str) {
+        self.0.push_str(&format!(
+            "<link rel=\"{rel}\" href=\"{}\">",
+            escape_attr(href)
+        ));
+    }
+
+    fn feed(&mut self, kind: &str, title: &str, href: &str) {
+        self.0.push_str(&format!(
+            "<link rel=\"alternate\" type=\"application/{kind}+xml\" title=\"{}\" href=\"{}\">",
+            escape_attr(title),
+            escape_attr(href)
+        ));
+    }
+}
+
 /// A page
 #[derive(Clone, Debug, PartialEq)]
 pub struct Page {
     sections: Vec<Section>,
+    metadata: Metadata,
+    outline: Vec<Heading>,
 }
 
 impl Page {
@@ -57,35 +260,418 @@ impl Page {
 impl Page {
     /// Read a page from a reader
     pub fn new<R: std::io::BufRead>(source: R) -> Result<Self, PageParseError> {
+        let mut sections = Reader::new(source).next_sections(None)?;
+        footnotes::collect_footnotes(&mut sections);
+        let outline = section::assign_heading_ids(&mut sections);
+        section::fill_toc(&mut sections, &outline);
+        let mut metadata = Metadata::default();
+        let mut names = HashSet::new();
+        for section in &sections {
+            if let Section::Metadata { data } = section {
+                if let Some(name) = data.keys().find(|name| !names.insert(name.as_str())) {
+                    return Err(PageParseError::DuplicateMetadataKey(name.clone()));
+                }
+                metadata.extend(data)?;
+            }
+        }
+        if metadata.title.is_none() {
+            metadata.title = sections.iter().find_map(|section| match section {
+                Section::Text { class, content, .. }
+                    if class
+                        .as_ref()
+                        .is_some_and(|class| class.iter().any(|class| class == "title")) =>
+                {
+                    Some(section::plain_text(content))
+                }
+                _ => None,
+            });
+        }
+
         Ok(Self {
-            sections: Reader::new(source).next_sections(None)?,
+            sections,
+            metadata,
+            outline,
         })
     }
 
-    /// Convert a page to [build_html::html_page::HtmlPage]
+    /// Page metadata
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Tree of page headings, including headings inside containers
+    pub fn outline(&self) -> &[Heading] {
+        &self.outline
+    }
+
+    /// Use metadata defaults for fields, that the page doesn't set
+    pub fn with_defaults(mut self, defaults: &Metadata) -> Self {
+        let mut metadata = defaults.clone();
+        metadata.merge(&self.metadata);
+        self.metadata = metadata;
+        self
+    }
+
+    /// Theme of the page. Selected in page metadata or in site config
+    pub fn theme(&self, options: &BuildOptions) -> Result<&'static Theme, PageBuildError> {
+        let name = self
+            .metadata
+            .text("theme")
+            .or_else(|| options.config.theme.clone())
+            .unwrap_or_else(|| DEFAULT_THEME.to_owned());
+        Theme::find(&name).ok_or(PageBuildError::UnknownTheme(name))
+    }
+
+    /// Code highlighting theme of the page. Selected in page metadata or in site config
+    pub fn highlight_theme(
+        &self,
+        options: &BuildOptions,
+    ) -> Result<&'static HighlightTheme, PageBuildError> {
+        let name = self
+            .metadata
+            .text("highlight-theme")
+            .or_else(|| options.config.highlight_theme.clone())
+            .unwrap_or_else(|| crate::highlight::DEFAULT_THEME.to_owned());
+        HighlightTheme::find(&name).ok_or(PageBuildError::UnknownHighlightTheme(name))
+    }
+
+    /// Convert a page to [build_html::HtmlPage]
+    #[deprecated(note = "use `Page::to_html_string`, that renders the page with theme layout")]
     pub fn to_html(&self, project_root: &Path) -> Result<HtmlPage, PageBuildError> {
+        #[allow(deprecated)]
+        self.to_html_with(project_root, &BuildOptions::default())
+    }
+
+    /// Convert a page to [build_html::HtmlPage] with custom options.
+    /// It has theme stylesheets and scripts, but not theme layout
+    #[deprecated(note = "use `Page::to_html_string_with`, that renders the page with theme layout")]
+    pub fn to_html_with(
+        &self,
+        project_root: &Path,
+        options: &BuildOptions,
+    ) -> Result<HtmlPage, PageBuildError> {
+        let config = &options.config;
+        let theme = self.theme(options)?;
+        let highlight_theme = self.highlight_theme(options)?;
         let mut page = HtmlPage::new();
-        page.add_head_link(
-            "https://cdnjs.cloudflare.com/ajax/libs/highlight.js/11.8.0/styles/github-dark.min.css",
-            "stylesheet",
-        );
-        page.add_script_link(
-            "https://cdnjs.cloudflare.com/ajax/libs/highlight.js/11.8.0/highlight.min.js",
-        );
-        page.add_head_link(
-            project_root.join("global.css").to_string_lossy().as_ref(),
-            "stylesheet",
-        );
-        page.add_script_literal("hljs.highlightAll();");
-        for section in &self.sections {
-            page.add_html(section.to_html(project_root)?);
+        if let Some(title) = &self.metadata.title {
+            page.add_title(title);
+        }
+        if config.extensions.server_highlight() {
+            page.add_stylesheet(
+                project_root
+                    .join(highlight_theme.stylesheet_path())
+                    .to_string_lossy(),
+            );
+        } else if config.extensions.highlight() {
+            let local = config.extensions.local_assets();
+            let (href, _) = vendor::link(project_root, &highlight_theme.hljs_path(), local);
+            page.add_stylesheet(href);
+            let (src, _) = vendor::link(project_root, crate::highlight::HLJS_SCRIPT, local);
+            page.add_script_link(src);
+            page.add_script_literal("hljs.highlightAll();");
+        }
+        for stylesheet in theme.stylesheet_paths() {
+            page.add_stylesheet(project_root.join(stylesheet).to_string_lossy());
         }
+        for script in theme.script_paths() {
+            page.add_script_link(project_root.join(script).to_string_lossy());
+        }
+        page.add_html(self.body_to_html(project_root, options)?);
         Ok(page)
     }
 
     /// Convert a page to a string, containing HTML for it
     pub fn to_html_string(&self, page_path: &Path) -> Result<String, PageBuildError> {
-        Ok(self.to_html(page_path)?.to_html_string())
+        self.to_html_string_with(page_path, &BuildOptions::default())
+    }
+
+    /// Categories of the page, from `-- categories` sections and `category`/`categories`
+    /// in metadata. Categories without a slug are skipped, duplicates are removed
+    pub fn categories(&self) -> Vec<String> {
+        let sections = self.sections.iter().flat_map(|section| match section {
+            Section::Categories { categories } => categories.clone(),
+            _ => Vec::new(),
+        });
+        unique_categories(sections.chain(self.metadata_categories()))
+    }
+
+    /// Categories from `category`/`categories` in metadata, without duplicates
+    fn metadata_categories(&self) -> Vec<String> {
+        let categories = ["category", "categories"]
+            .into_iter()
+            .filter_map(|name| self.metadata.get(name))
+            .flat_map(|value| {
+                value
+                    .as_list()
+                    .iter()
+                    .map(Value::to_string)
+                    .collect::<Vec<_>>()
+            });
+        unique_categories(categories)
+    }
+
+    /// Should the page be built: it's not a draft or scheduled, unless options allow it
+    pub fn is_published(&self, options: &BuildOptions) -> bool {
+        self.metadata.is_published(options)
+    }
+
+    /// Name of the layout template, selected with `-- template: name` in metadata.
+    /// Names with path separators or `..` are rejected
+    pub fn template(&self) -> Result<Option<String>, PageBuildError> {
+        match self.metadata.text("template") {
+            Some(name) if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") => {
+                Err(PageBuildError::InvalidTemplateName(name))
+            }
+            name => Ok(name),
+        }
+    }
+
+    /// Convert a page to a string, containing HTML for it, with custom options
+    pub fn to_html_string_with(
+        &self,
+        project_root: &Path,
+        options: &BuildOptions,
+    ) -> Result<String, PageBuildError> {
+        self.to_html_string_with_layout(project_root, options, None)
+    }
+
+    /// Convert a page to a string, containing HTML for it, with custom options.
+    /// `layout` is a [template](crate::template), that replaces theme layout
+    pub fn to_html_string_with_layout(
+        &self,
+        project_root: &Path,
+        options: &BuildOptions,
+        layout: Option<&str>,
+    ) -> Result<String, PageBuildError> {
+        let config = &options.config;
+        let theme = self.theme(options)?;
+        let highlight_theme = self.highlight_theme(options)?;
+        let mut head = Head::default();
+        self.add_meta_tags(&mut head, options);
+        if config.extensions.server_highlight() {
+            head.stylesheet(
+                &project_root
+                    .join(highlight_theme.stylesheet_path())
+                    .to_string_lossy(),
+            );
+        } else if config.extensions.highlight() {
+            let local = config.extensions.local_assets();
+            head.vendored_stylesheet(vendor::link(
+                project_root,
+                &highlight_theme.hljs_path(),
+                local,
+            ));
+            head.vendored_script(vendor::link(
+                project_root,
+                crate::highlight::HLJS_SCRIPT,
+                local,
+            ));
+        }
+        for stylesheet in theme.stylesheet_paths() {
+            head.stylesheet(&project_root.join(stylesheet).to_string_lossy());
+        }
+        for stylesheet in &config.head.stylesheets {
+            head.stylesheet(&asset_link(project_root, stylesheet));
+        }
+        for script in theme.script_paths() {
+            head.script(&project_root.join(script).to_string_lossy());
+        }
+        for script in &config.head.scripts {
+            head.script(&asset_link(project_root, script));
+        }
+        if config.extensions.highlight() && !config.extensions.server_highlight() {
+            head.script_literal("hljs.highlightAll();");
+        }
+        if options.dev {
+            head.script_literal(&format!(
+                "new EventSource(\"{LIVE_RELOAD_PATH}\").onmessage = () => location.reload();"
+            ));
+        }
+
+        let mut body = String::new();
+        if self.metadata.is_draft() {
+            body.push_str("<div class=\"draft-banner\">Draft</div>");
+        } else if let Some(date) = self.metadata.date.filter(|_| self.metadata.is_scheduled()) {
+            body.push_str(&format!(
+                "<div class=\"draft-banner\">Scheduled for {}</div>",
+                date.format("%Y-%m-%d %H:%M")
+            ));
+        }
+        body.push_str(&self.body_to_html(project_root, options)?);
+        let context = self.template_context(project_root, options, head.0, body);
+        match layout {
+            Some(layout) => crate::template::render(layout, &context),
+            None => theme.render(&context),
+        }
+        .map_err(PageBuildError::Template)
+    }
+
+    /// HTML of page sections, without layout
+    pub fn body_to_html(
+        &self,
+        project_root: &Path,
+        options: &BuildOptions,
+    ) -> Result<String, PageBuildError> {
+        let mut body = String::new();
+        for section in &self.sections {
+            body.push_str(&section.to_html(project_root, options)?);
+        }
+        Ok(body)
+    }
+
+    /// Template variables: `head`, `body` (also `content`), `toc`, `nav`, `pager`,
+    /// `categories` (links to categories from metadata, `-- categories` sections link to
+    /// theirs in place), `lang`, `root`, `title`, `site_title`, `url`, `description`,
+    /// `author`, `date`, `updated`, `tags` and `meta.<name>` for other metadata fields
+    fn template_context(
+        &self,
+        project_root: &Path,
+        options: &BuildOptions,
+        head: String,
+        body: String,
+    ) -> Context {
+        let metadata = &self.metadata;
+        let mut context = Context::default();
+        context.html("head", head);
+        context.html("content", body.clone());
+        context.html("body", body);
+        context.html(
+            "toc",
+            outline::outline_to_html(&self.outline, options.config.toc_depth()),
+        );
+        context.html(
+            "nav",
+            nav::nav_to_html(&options.nav, project_root, options.path.as_deref()),
+        );
+        context.html(
+            "pager",
+            nav::pager_to_html(options.prev.as_ref(), options.next.as_ref(), project_root),
+        );
+        context.html(
+            "categories",
+            section::categories_to_html(&self.metadata_categories(), project_root),
+        );
+        context.text(
+            "lang",
+            Some(
+                metadata
+                    .lang
+                    .as_deref()
+                    .or(options.lang.as_deref())
+                    .unwrap_or("en"),
+            ),
+        );
+        let root = project_root.to_string_lossy();
+        context.text(
+            "root",
+            Some(if root.is_empty() {
+                String::new()
+            } else {
+                format!("{root}/")
+            }),
+        );
+        context.text(
+            "title",
+            metadata.title.as_ref().or(options.site_title.as_ref()),
+        );
+        context.text("site_title", options.site_title.as_ref());
+        context.text("url", options.url());
+        context.text("description", metadata.description.as_ref());
+        context.text("author", metadata.author.as_ref());
+        let format_date = |date: &Date| date.format("%Y-%m-%d").to_string();
+        context.text("date", metadata.date.as_ref().map(format_date));
+        context.text("updated", metadata.updated.as_ref().map(format_date));
+        context.list("tags", metadata.tags.clone());
+        for name in metadata.extra.keys() {
+            match metadata.get(name) {
+                Some(Value::List(list)) => context.list(
+                    format!("meta.{name}"),
+                    list.iter().map(Value::to_string).collect(),
+                ),
+                _ => context.text(format!("meta.{name}"), metadata.text(name)),
+            }
+        }
+        context
+    }
+
+    /// Charset, title, description, canonical link, Open Graph and Twitter tags
+    fn add_meta_tags(&self, head: &mut Head, options: &BuildOptions) {
+        let metadata = &self.metadata;
+        head.0.push_str("<meta charset=\"utf-8\">");
+        head.meta("viewport", "width=device-width, initial-scale=1");
+
+        let title = match (&metadata.title, &options.site_title) {
+            (Some(title), Some(site_title)) if title != site_title => {
+                Some(format!("{title} - {site_title}"))
+            }
+            (Some(title), _) | (None, Some(title)) => Some(title.clone()),
+            (None, None) => None,
+        };
+        if let Some(title) = &title {
+            head.title(title);
+        }
+        if let Some(description) = &metadata.description {
+            head.meta("description", description);
+        }
+        if let Some(author) = &metadata.author {
+            head.meta("author", author);
+        }
+        if !metadata.tags.is_empty() {
+            head.meta("keywords", &metadata.tags.join(", "));
+        }
+
+        let url = options.url();
+        if let Some(url) = &url {
+            head.link("canonical", url);
+        }
+        if metadata.is_noindex() {
+            head.meta("robots", "noindex");
+        }
+        if let Some(base_url) = options.base_url.as_ref().filter(|_| options.feeds) {
+            let base_url = base_url.trim_end_matches('/');
+            let title = options.site_title.as_deref().unwrap_or_default();
+            head.feed("rss", title, &format!("{base_url}/{FEED_FILE}"));
+            head.feed("atom", title, &format!("{base_url}/{ATOM_FILE}"));
+        }
+
+        // * Open Graph
+        if let Some(title) = metadata.title.as_ref().or(options.site_title.as_ref()) {
+            head.property("og:title", title);
+        }
+        if let Some(description) = &metadata.description {
+            head.property("og:description", description);
+        }
+        if let Some(site_title) = &options.site_title {
+            head.property("og:site_name", site_title);
+        }
+        if let Some(url) = &url {
+            head.property("og:url", url);
+        }
+        if let Some(date) = &metadata.date {
+            head.property("og:type", "article");
+            head.property("article:published_time", &date.to_rfc3339());
+            if let Some(updated) = &metadata.updated {
+                head.property("article:modified_time", &updated.to_rfc3339());
+            }
+            if let Some(author) = &metadata.author {
+                head.property("article:author", author);
+            }
+            for tag in &metadata.tags {
+                head.property("article:tag", tag);
+            }
+        } else {
+            head.property("og:type", "website");
+        }
+
+        // * Twitter
+        head.meta("twitter:card", "summary");
+        if let Some(title) = metadata.title.as_ref().or(options.site_title.as_ref()) {
+            head.meta("twitter:title", title);
+        }
+        if let Some(description) = &metadata.description {
+            head.meta("twitter:description", description);
+        }
     }
 }
 
@@ -159,13 +745,7 @@ impl<R: std::io::BufRead> Reader<R> {
     ) -> Result<String, PageParseError> {
         self.skip_blanks()?;
         let mut text = String::new();
-        loop {
-            let line = if let Some(line) = self.next_line_if_map(&mut filter_map)? {
-                line
-            } else {
-                break;
-            };
-
+        while let Some(line) = self.next_line_if_map(&mut filter_map)? {
             #[allow(clippy::collapsible_else_if)]
             if raw {
                 text.push_str(&line);
@@ -235,17 +815,24 @@ impl<R: std::io::BufRead> Reader<R> {
     }
 
     // * ----------------------------------- Specials ----------------------------------- * //
+    /// Attribute line: `-- name` or `-- name: value`. A line ending with `\`
+    /// continues on the next line
     pub(super) fn next_attr(&mut self) -> Result<Option<Attribute>, PageParseError> {
-        if let Some(line) = self.next_line_if(has_attr_prefix)? {
-            if let Some(attr) = strip_attr_prefix(&line) {
-                if let Some(attr) = Attribute::parse(attr)? {
-                    return Ok(Some(attr));
-                } else {
-                    self.peek = Some(line);
-                }
-            }
+        let Some(mut attr) = self.next_line_if_map(|line| {
+            strip_attr_prefix(line).filter(|attr| Attribute::is_attribute(attr))
+        })?
+        else {
+            return Ok(None);
+        };
+        while let Some(head) = attr.strip_suffix('\\') {
+            let head = head.trim_end().to_owned();
+            let Some(line) = self.next_line()? else {
+                attr = head;
+                break;
+            };
+            attr = format!("{head} {}", line.trim());
         }
-        Ok(None)
+        Attribute::parse(&attr)
     }
 
     pub(super) fn next_attrs(&mut self) -> Result<Vec<Attribute>, PageParseError> {
@@ -256,6 +843,60 @@ impl<R: std::io::BufRead> Reader<R> {
         Ok(attrs)
     }
 
+    /// Metadata lines: `-- name: value`. Indented lines continue the value of previous line,
+    /// joined with a space. Values are checked by [Value::parse], but kept as written
+    pub(super) fn next_metadata(&mut self) -> Result<HashMap<String, String>, PageParseError> {
+        fn parse_entry(
+            data: &mut HashMap<String, String>,
+            (name, value): (String, String),
+        ) -> Result<(), PageParseError> {
+            Value::parse(&value)
+                .map_err(|err| PageParseError::WrongMetadataFormat(format!("{name}: {err}")))?;
+            if data.insert(name.clone(), value).is_some() {
+                return Err(PageParseError::DuplicateMetadataKey(name));
+            }
+            Ok(())
+        }
+
+        self.skip_blanks()?;
+        let mut data = HashMap::new();
+        let mut entry: Option<(String, String)> = None;
+        loop {
+            if let Some(line) = self.next_line_if_map(strip_attr_prefix)? {
+                if let Some(entry) = entry.take() {
+                    parse_entry(&mut data, entry)?;
+                }
+                let (name, value) = line
+                    .split_once(':')
+                    .ok_or_else(|| PageParseError::WrongMetadataFormat(line.clone()))?;
+                let name = name.trim();
+                if name.is_empty() || !name.chars().all(attribute::is_name_char) {
+                    return Err(PageParseError::WrongMetadataFormat(format!(
+                        "Invalid name '{name}'"
+                    )));
+                }
+                entry = Some((name.to_owned(), value.trim().to_owned()));
+            } else if let Some((_, value)) = &mut entry {
+                let Some(line) = self.next_line_if(|line| {
+                    line.starts_with(char::is_whitespace) && !line.trim().is_empty()
+                })?
+                else {
+                    break;
+                };
+                if !value.is_empty() {
+                    value.push(' ');
+                }
+                value.push_str(line.trim());
+            } else {
+                break;
+            }
+        }
+        if let Some(entry) = entry {
+            parse_entry(&mut data, entry)?;
+        }
+        Ok(data)
+    }
+
     pub(super) fn next_list(
         &mut self,
         filter: impl Fn(&str) -> bool,
@@ -382,9 +1023,15 @@ pub enum PageParseError {
     /// Unexpected argument
     #[error("Unexpected argument '{0}' in attribute '{1}', this attribute is ment to be used without arguments")]
     UnexpectedArgument(String, String),
+    /// Attribute value can't be parsed
+    #[error("Wrong value of attribute '{0}': {1}")]
+    WrongAttributeValue(String, String),
     /// Wrong metadata format
     #[error("Wrong metadata format: {0}")]
     WrongMetadataFormat(String),
+    /// The same metadata field is set twice in one section
+    #[error("Duplicate metadata field: '{0}'")]
+    DuplicateMetadataKey(String),
     /// Title/Subtitle section is empty
     #[error("Title/Subtitle section is empty!")]
     EmptyTitle,
@@ -402,4 +1049,16 @@ pub enum PageBuildError {
     /// Failed to find relative path to project file
     #[error("Failed to find relative path to project file from file '{0}'")]
     RelativePathNotFound(String),
+    /// Theme doesn't exist
+    #[error("Unknown theme: '{0}'")]
+    UnknownTheme(String),
+    /// Highlighting theme doesn't exist
+    #[error("Unknown highlight theme: '{0}'")]
+    UnknownHighlightTheme(String),
+    /// Layout template can't be rendered
+    #[error("Template error: {0}")]
+    Template(String),
+    /// Template name is not a file name in templates directory
+    #[error("Invalid template name '{0}', expected a name without path separators or '..'")]
+    InvalidTemplateName(String),
 }
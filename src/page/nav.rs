@@ -0,0 +1,107 @@
+use crate::escape::escape_attr;
+use std::path::{Path, PathBuf};
+
+/// A page or a directory in site navigation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NavItem {
+    /// Page title, or title of directory index page, or directory name
+    pub title: String,
+    /// Chapter number in book mode, like "1.2."
+    pub number: Option<String>,
+    /// Path of HTML file, relative to output root. [None] for directories without index page
+    pub path: Option<PathBuf>,
+    /// Pages and directories inside a directory
+    pub children: Vec<NavItem>,
+}
+
+impl NavItem {
+    /// Is it the current page or does it contain the current page
+    fn contains(&self, current: &Path) -> bool {
+        self.path.as_deref() == Some(current)
+            || self.children.iter().any(|child| child.contains(current))
+    }
+}
+
+fn link(item: &NavItem, project_root: &Path, current: Option<&Path>) -> String {
+    let title = match &item.number {
+        Some(number) => format!(
+            "<strong>{}</strong> {}",
+            escape_attr(number),
+            escape_attr(&item.title)
+        ),
+        None => escape_attr(&item.title),
+    };
+    match &item.path {
+        Some(path) if Some(path.as_path()) == current => format!(
+            "<a class=\"current\" aria-current=\"page\" href=\"{}\">{title}</a>",
+            escape_attr(&project_root.join(path).to_string_lossy())
+        ),
+        Some(path) => format!(
+            "<a href=\"{}\">{title}</a>",
+            escape_attr(&project_root.join(path).to_string_lossy())
+        ),
+        None => format!("<span>{title}</span>"),
+    }
+}
+
+fn items_to_html(items: &[NavItem], project_root: &Path, current: Option<&Path>) -> String {
+    let mut html = String::from("<ul>");
+    for item in items {
+        html.push_str("<li>");
+        if item.children.is_empty() {
+            html.push_str(&link(item, project_root, current));
+        } else {
+            let open = current.is_some_and(|current| item.contains(current));
+            html.push_str(&format!(
+                "<details{}><summary>{}</summary>{}</details>",
+                if open { " open" } else { "" },
+                link(item, project_root, current),
+                items_to_html(&item.children, project_root, current)
+            ));
+        }
+        html.push_str("</li>");
+    }
+    html.push_str("</ul>");
+    html
+}
+
+/// Links to previous and next pages. Empty if there are none
+pub fn pager_to_html(
+    prev: Option<&(String, PathBuf)>,
+    next: Option<&(String, PathBuf)>,
+    project_root: &Path,
+) -> String {
+    if prev.is_none() && next.is_none() {
+        return String::new();
+    }
+    let href = |path: &Path| escape_attr(&project_root.join(path).to_string_lossy());
+    let mut html = String::from("<nav class=\"pager\">");
+    if let Some((title, path)) = prev {
+        html.push_str(&format!(
+            "<a rel=\"prev\" href=\"{}\">← {}</a>",
+            href(path),
+            escape_attr(title)
+        ));
+    }
+    if let Some((title, path)) = next {
+        html.push_str(&format!(
+            "<a rel=\"next\" href=\"{}\">{} →</a>",
+            href(path),
+            escape_attr(title)
+        ));
+    }
+    html.push_str("</nav>");
+    html
+}
+
+/// Navigation menu with links relative to project root. Directories are collapsible,
+/// the ones with the current page are expanded. Empty if there are no items
+pub fn nav_to_html(items: &[NavItem], project_root: &Path, current: Option<&Path>) -> String {
+    if items.is_empty() {
+        return String::new();
+    }
+    format!(
+        "<nav class=\"site-nav\">{}</nav>",
+        items_to_html(items, project_root, current)
+    )
+}
@@ -0,0 +1,48 @@
+use crate::escape::escape_attr;
+
+/// A heading in the page outline
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Heading {
+    /// Heading level: 1 for `h1`
+    pub level: usize,
+    /// Generated or explicit id of the heading
+    pub id: String,
+    /// Heading text without formatting
+    pub text: String,
+    /// Headings, that follow this one and have a higher level
+    pub children: Vec<Heading>,
+}
+
+/// Tree of headings, in order of the page
+pub(super) fn nest(headings: Vec<Heading>) -> Vec<Heading> {
+    let mut outline = Vec::new();
+    for heading in headings {
+        let mut level = &mut outline;
+        while level
+            .last()
+            .is_some_and(|parent: &Heading| parent.level < heading.level)
+        {
+            level = &mut level.last_mut().unwrap().children;
+        }
+        level.push(heading);
+    }
+    outline
+}
+
+/// Nested list of links to headings, `depth` levels deep. Empty if there are no headings
+pub fn outline_to_html(outline: &[Heading], depth: usize) -> String {
+    if outline.is_empty() || depth == 0 {
+        return String::new();
+    }
+    let mut html = String::from("<ul>");
+    for heading in outline {
+        html.push_str(&format!(
+            "<li><a href=\"#{}\">{}</a>{}</li>",
+            escape_attr(&heading.id),
+            escape_attr(&heading.text),
+            outline_to_html(&heading.children, depth - 1)
+        ));
+    }
+    html.push_str("</ul>");
+    html
+}
@@ -1,5 +1,8 @@
-use super::attribute::Attribute;
-use super::{PageBuildError, PageParseError};
+use super::attribute::{parse_inline_attrs, Attribute};
+use super::footnotes::Footnote;
+use super::outline::{self, outline_to_html, Heading};
+use super::{BuildOptions, PageBuildError, PageParseError};
+use crate::escape::escape_html;
 use itertools::Itertools;
 use std::collections::HashMap;
 use std::path::Path;
@@ -98,6 +101,16 @@ pub enum Section {
     Metadata { data: HashMap<String, String> },
     /// cathegories
     Categories { categories: Vec<String> },
+    /// Rendered HTML, like chapters of a [combined](super::Page::combine) page
+    Html { content: String },
+    /// Footnotes, collected at the end of a page or a container
+    Footnotes { notes: Vec<Footnote> },
+    /// toc
+    Toc {
+        attributes: Vec<Attribute>,
+        depth: Option<usize>,
+        outline: Vec<Heading>,
+    },
 }
 
 // * ------------------------------------- Parse ------------------------------------ * //
@@ -136,7 +149,7 @@ impl Section {
                 }
                 .to_owned(),
                 class: match section {
-                    "title" | "subtitle" => Some(vec![section.to_owned()]),
+                    "title" | "subtitle" | "footnote" => Some(vec![section.to_owned()]),
                     _ => None,
                 },
 
@@ -298,18 +311,7 @@ impl Section {
                 content: source.next_text_until_section(true)?,
             }),
             "metadata" => Ok(Self::Metadata {
-                data: {
-                    let mut meta = HashMap::new();
-                    for metaline in source.next_text_prefixed("--", true)?.split('\n') {
-                        let mut name = String::new();
-                        let mut value = String::new();
-                        scanf::sscanf!(metaline, "{}:{}", name, value).map_err(|_| {
-                            PageParseError::WrongMetadataFormat(metaline.to_owned())
-                        })?;
-                        meta.insert(name.trim().to_owned(), value.trim().to_owned());
-                    }
-                    meta
-                },
+                data: source.next_metadata()?,
             }),
             "categories" => Ok(Self::Categories {
                 categories: source
@@ -319,14 +321,176 @@ impl Section {
                     .map(str::to_owned)
                     .collect(),
             }),
+            "toc" => {
+                let attributes = source.next_attrs()?;
+                let depth = attr!(attributes, Depth)
+                    .map(|depth| {
+                        depth.parse().map_err(|_| {
+                            PageParseError::WrongAttributeValue(
+                                String::from("depth"),
+                                format!("Expected a number, got '{depth}'"),
+                            )
+                        })
+                    })
+                    .transpose()?;
+                Ok(Self::Toc {
+                    attributes,
+                    depth,
+                    outline: Vec::new(),
+                })
+            }
             _ => Err(PageParseError::UnknownSection(section.to_owned())),
         }
     }
 }
 
+// * ----------------------------------- Headings ----------------------------------- * //
+/// Heading level of a tag: 1 for `h1`, [None] for other tags
+pub(super) fn heading_level(tag: &str) -> Option<usize> {
+    match tag.strip_prefix('h')?.parse() {
+        Ok(level @ 1..=6) => Some(level),
+        _ => None,
+    }
+}
+
+/// Text without formatting, links and footnote references, with escapes resolved
+/// Works on source text, like [text_to_html], but keeps only the text
+pub(super) fn plain_text(text: &str) -> String {
+    // Escaped characters are moved into private use area, so they don't start formatting
+    const ESCAPED: u32 = 0xE000;
+    let text = regex!(r"\\([\\<>*_~`^])").replace_all(text, |captures: &regex::Captures| {
+        let c = captures[1].chars().next().unwrap_or_default();
+        char::from_u32(ESCAPED + c as u32).map_or_else(String::new, String::from)
+    });
+
+    let text = regex!(r"<<(\w+)\s*\|([^|]*?)>>").replace_all(&text, "");
+    let text =
+        regex!(r"<<(\w+)\s*\|(.*?)\|(.*?)>>").replace_all(&text, |captures: &regex::Captures| {
+            match &captures[1] {
+                "fnref" | "img" => String::new(),
+                _ => captures[2].to_owned(),
+            }
+        });
+    let text = regex!(r">(.*?)>(.*?)>").replace_all(&text, "$1");
+    let text = regex!(r"<(.*?)>").replace_all(&text, "$1");
+    let text = regex!(r"\*(.*?)\*(.*?)\*").replace_all(&text, "$1");
+    let text = regex!(r"_(.*?)_(.*?)_").replace_all(&text, "$1");
+    let text = regex!(r"\~(.*?)\~(.*?)\~").replace_all(&text, "$1");
+    let text = regex!(r"`(.*?)`(.*?)`").replace_all(&text, "$1");
+    text.chars()
+        .map(|c| match c as u32 {
+            code @ ESCAPED..0xE080 => char::from_u32(code - ESCAPED).unwrap_or(c),
+            _ => c,
+        })
+        .collect::<String>()
+        .replace('\n', " ")
+}
+
+impl Section {
+    /// Attributes of the section, if it has any
+    fn attributes(&self) -> &[Attribute] {
+        match self {
+            Self::Text { attributes, .. }
+            | Self::TextWrapper { attributes, .. }
+            | Self::Container { attributes, .. }
+            | Self::Code { attributes, .. }
+            | Self::Tag { attributes, .. }
+            | Self::Bookmark { attributes, .. }
+            | Self::Notes { attributes, .. }
+            | Self::List { attributes, .. }
+            | Self::Checklist { attributes, .. }
+            | Self::Image { attributes, .. }
+            | Self::Toc { attributes, .. } => attributes,
+            _ => &[],
+        }
+    }
+
+    /// Call a function for this section and every nested section
+    pub(super) fn visit_mut(&mut self, f: &mut impl FnMut(&mut Section)) {
+        f(self);
+        if let Self::Container { content, .. } = self {
+            for section in content {
+                section.visit_mut(f);
+            }
+        }
+    }
+}
+
+/// Give every heading an id: its explicit `-- id` or a slug of its text.
+/// Generated ids don't collide with other ids on the page: "intro", "intro-1", ...
+/// Returns the outline of the page
+pub(super) fn assign_heading_ids(sections: &mut [Section]) -> Vec<Heading> {
+    let mut ids = std::collections::HashSet::new();
+    for section in sections.iter_mut() {
+        section.visit_mut(&mut |section| {
+            if let Some(id) = attr!(section.attributes(), Id) {
+                ids.insert(id.clone());
+            }
+        });
+    }
+    let mut headings = Vec::new();
+    for section in sections.iter_mut() {
+        section.visit_mut(&mut |section| {
+            let Section::Text {
+                tag,
+                attributes,
+                content,
+                ..
+            } = section
+            else {
+                return;
+            };
+            let Some(level) = heading_level(tag) else {
+                return;
+            };
+            let text = plain_text(content);
+            let id = match attr!(attributes, Id) {
+                Some(id) => id.clone(),
+                None => {
+                    let mut slug = super::slugify(&text);
+                    if slug.is_empty() {
+                        slug = String::from("section");
+                    }
+                    let mut id = slug.clone();
+                    let mut index = 1;
+                    while ids.contains(&id) {
+                        id = format!("{slug}-{index}");
+                        index += 1;
+                    }
+                    ids.insert(id.clone());
+                    attributes.push(Attribute::Id(id.clone()));
+                    id
+                }
+            };
+            headings.push(Heading {
+                level,
+                id,
+                text,
+                children: Vec::new(),
+            });
+        });
+    }
+    outline::nest(headings)
+}
+
+/// Fill `-- toc` sections with the outline of the page
+pub(super) fn fill_toc(sections: &mut [Section], page_outline: &[Heading]) {
+    for section in sections.iter_mut() {
+        section.visit_mut(&mut |section| {
+            if let Section::Toc { outline, .. } = section {
+                *outline = page_outline.to_vec();
+            }
+        });
+    }
+}
+
 // * ------------------------------------- Build ------------------------------------ * //
 impl Section {
-    pub(super) fn to_html(&self, project_root: &Path) -> Result<String, PageBuildError> {
+    pub(super) fn to_html(
+        &self,
+        project_root: &Path,
+        options: &BuildOptions,
+    ) -> Result<String, PageBuildError> {
         // * Attrs
         macro_rules! attributes {
             ($attrs: expr) => {{
@@ -361,14 +525,27 @@ impl Section {
         }
 
         // * Utils
-        fn format_code(content: &str, title: String, attributes: String) -> String {
-            format!(
-                "<pre>{}<code{}>{}</code></pre>",
-                title,
-                attributes,
-                escape_html(content),
-            )
-        }
+        let format_code =
+            |content: &str, title: String, attributes: String, language: Option<&str>| {
+                match language
+                    .filter(|_| options.config.extensions.server_highlight())
+                    .and_then(|language| crate::highlight::highlight(content, language))
+                {
+                    Some(highlighted) => format!(
+                        "<pre class=\"{}\">{}<code{}>{}</code></pre>",
+                        crate::highlight::CLASS,
+                        title,
+                        attributes,
+                        highlighted,
+                    ),
+                    None => format!(
+                        "<pre>{}<code{}>{}</code></pre>",
+                        title,
+                        attributes,
+                        escape_html(content),
+                    ),
+                }
+            };
 
         match self {
             Self::Text {
@@ -377,7 +554,7 @@ impl Section {
                 attributes,
                 content,
             } => Ok(format!(
-                "<{tag}{}{}>{}{}</{tag}>",
+                "<{tag}{}{}>{}{}{}</{tag}>",
                 match class {
                     Some(classes) => format!(
                         " class=\"{}\"",
@@ -389,7 +566,16 @@ impl Section {
                 },
                 attributes!(attributes),
                 title!(attributes),
-                text_to_html(project_root, content)
+                text_to_html(project_root, content),
+                match attr!(attributes, Id) {
+                    Some(id)
+                        if heading_level(tag).is_some()
+                            && options.config.extensions.heading_anchors() =>
+                    {
+                        format!("<a class=\"anchor\" href=\"#{id}\" aria-hidden=\"true\">¶</a>")
+                    }
+                    _ => String::new(),
+                }
             )),
             Self::TextWrapper {
                 tag,
@@ -412,7 +598,7 @@ impl Section {
                 {
                     let mut html = String::new();
                     for section in content {
-                        html.push_str(&section.to_html(project_root)?);
+                        html.push_str(&section.to_html(project_root, options)?);
                     }
                     html
                 },
@@ -422,11 +608,21 @@ impl Section {
                 attributes,
                 content,
             } => Ok(match tag.as_str() {
-                "code" => format_code(content, title!(attributes), attributes!(attributes)),
+                "code" => format_code(
+                    content,
+                    title!(attributes),
+                    attributes!(attributes),
+                    attributes.iter().find_map(|attr| match attr {
+                        Attribute::Class(class) => class
+                            .split_whitespace()
+                            .find_map(|class| class.strip_prefix("language-")),
+                        _ => None,
+                    }),
+                ),
                 tag => {
                     format!("<{tag}{}>{}</{tag}>", attributes!(attributes), content)
                         + &if has_attr!(attributes, Show) {
-                            format_code(content, title!(attributes), String::new())
+                            format_code(content, title!(attributes), String::new(), None)
                         } else {
                             String::new()
                         }
@@ -544,34 +740,54 @@ impl Section {
 
             Self::Hidden { content } => Ok(format!("<!-- {} -->", escape_html(content))),
             Self::Metadata { data: _ } => Ok(String::new()),
-            Self::Categories { categories: _ } => Ok(String::new()),
+            Self::Categories { categories } => Ok(categories_to_html(categories, project_root)),
+            Self::Html { content } => Ok(content.clone()),
+            Self::Footnotes { notes } => Ok(format!(
+                "<section class=\"footnotes\"><ol>{}</ol></section>",
+                notes
+                    .iter()
+                    .map(|note| format!(
+                        "<li id=\"fn-{0}\" value=\"{1}\">{2} <a class=\"footnote-backref\" href=\"#fnref-{0}\">↩</a></li>",
+                        note.anchor,
+                        note.number,
+                        text_to_html(project_root, &note.content)
+                    ))
+                    .collect::<String>()
+            )),
+            Self::Toc {
+                attributes,
+                depth,
+                outline,
+            } => Ok(format!(
+                "<nav class=\"toc\"{}>{}{}</nav>",
+                attributes!(attributes),
+                title!(attributes),
+                outline_to_html(outline, depth.unwrap_or(options.config.toc_depth()))
+            )),
         }
     }
 }
 
 // * -------------------------------- Text formatting ------------------------------- * //
-fn escape_html(code: &str) -> String {
-    code.replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-}
-
 fn text_to_html(project_root: &Path, text: &str) -> String {
     fn regex_replace<'a>(
         text: &'a str,
-        pattern: &str,
+        regex: &regex::Regex,
         replacer: impl Fn(&regex::Captures) -> String,
     ) -> std::borrow::Cow<'a, str> {
-        regex::Regex::new(&escape_html(pattern))
-            .unwrap()
-            .replace_all(text, replacer)
+        regex.replace_all(text, replacer)
+    }
+
+    // Patterns are written for source text, but match escaped HTML
+    macro_rules! pattern {
+        ($pattern: expr) => {
+            regex!(escape_html($pattern))
+        };
     }
 
     macro_rules! format_attrs {
         ($attrs: expr) => {
-            regex_replace(&$attrs, r"(\w+)\s*:\s*(\w+)\|?", |captures| {
-                format!("{} = \"{}\"", &captures[1], &captures[2])
-            })
+            format_inline_attrs(&$attrs)
         };
     }
 
@@ -604,43 +820,62 @@ fn text_to_html(project_root: &Path, text: &str) -> String {
     let text = text.replace("\\_", "&#x005f;");
     let text = text.replace("\\~", "&#x007e;");
     let text = text.replace("\\`", "&#x0060;");
+    let text = text.replace("\\^", "&#x005e;");
 
     // Tag
-    let text = regex_replace(&text, r"<<(\w+)\s*\|(.*?)>>", |captures| {
-        match &captures[1] {
-            "img" => format!("<img src=\"{}\" />", format_link(project_root, &captures[2])),
-            tag => format!("<{tag} {} />", format_attrs!(captures[2])),
-        }
-    });
-
     let text = regex_replace(
         &text,
-        r"<<(\w+)\s*\|(.*?)\|(.*?)>>",
+        pattern!(r"<<(\w+)\s*\|([^|]*?)>>"),
         |captures| match &captures[1] {
-            "link" => make_link(project_root, &captures[2], &captures[3]),
-            tag => wrap_tag!(tag, format_attrs!(captures[3]), &captures[2]),
+            "img" => format!(
+                "<img src=\"{}\" />",
+                format_link(project_root, &captures[2])
+            ),
+            tag => format!("<{tag} {} />", format_attrs!(captures[2])),
         },
     );
 
+    let text = regex_replace(&text, pattern!(r"<<(\w+)\s*\|(.*?)\|(.*?)>>"), |captures| {
+        match &captures[1] {
+            "link" => make_link(project_root, &captures[2], &captures[3]),
+            "fnref" => {
+                let (anchor, id) = match captures[3].split_once(':') {
+                    Some((anchor, reference)) => (anchor, format!("{anchor}-{reference}")),
+                    None => (&captures[3], captures[3].to_owned()),
+                };
+                format!(
+                    "<sup class=\"footnote-ref\"><a href=\"#fn-{anchor}\" id=\"fnref-{id}\">{}</a></sup>",
+                    &captures[2]
+                )
+            }
+            "img" => format!(
+                "<img src=\"{}\"{} />",
+                format_link(project_root, &captures[2]),
+                format_attrs!(captures[3])
+            ),
+            tag => wrap_tag!(tag, format_attrs!(captures[3]), &captures[2]),
+        }
+    });
+
     // Shortcuts
-    let text = regex_replace(&text, r">(.*?)>(.*?)>", |captures| {
+    let text = regex_replace(&text, pattern!(r">(.*?)>(.*?)>"), |captures| {
         make_link(project_root, &captures[1], &captures[2])
     });
 
-    let text = regex_replace(&text, r"<(.*?)>", |captures| {
+    let text = regex_replace(&text, pattern!(r"<(.*?)>"), |captures| {
         make_link(project_root, &captures[1], &captures[1])
     });
 
-    let text = regex_replace(&text, r"\*(.*?)\*(.*?)\*", |captures| {
+    let text = regex_replace(&text, pattern!(r"\*(.*?)\*(.*?)\*"), |captures| {
         wrap_tag!("strong", format_attrs!(captures[2]), &captures[1])
     });
-    let text = regex_replace(&text, r"_(.*?)_(.*?)_", |captures| {
+    let text = regex_replace(&text, pattern!(r"_(.*?)_(.*?)_"), |captures| {
         wrap_tag!("em", format_attrs!(captures[2]), &captures[1])
     });
-    let text = regex_replace(&text, r"\~(.*?)\~(.*?)\~", |captures| {
+    let text = regex_replace(&text, pattern!(r"\~(.*?)\~(.*?)\~"), |captures| {
         wrap_tag!("s", format_attrs!(captures[2]), &captures[1])
     });
-    let text = regex_replace(&text, r"`(.*?)`(.*?)`", |captures| {
+    let text = regex_replace(&text, pattern!(r"`(.*?)`(.*?)`"), |captures| {
         format!(
             "<code {}>{}</code>",
             (format_attrs!(captures[2])),
@@ -650,6 +885,17 @@ fn text_to_html(project_root: &Path, text: &str) -> String {
     text.replace('\n', "<br>")
 }
 
+/// Inline attributes, see [parse_inline_attrs]
+fn format_inline_attrs(attrs: &str) -> String {
+    parse_inline_attrs(attrs)
+        .into_iter()
+        .map(|(name, value)| match value {
+            Some(value) => format!(" {name} = \"{}\"", value.replace('"', "&quot;")),
+            None => format!(" {name}"),
+        })
+        .collect()
+}
+
 fn format_link(project_root: &Path, link: &str) -> String {
     if let Some(local_url) = link.strip_prefix('/') {
         return project_root
@@ -661,6 +907,27 @@ fn format_link(project_root: &Path, link: &str) -> String {
     link.to_owned()
 }
 
+/// Paragraph of links to category pages. Empty if no category has a slug
+pub(super) fn categories_to_html(categories: &[String], project_root: &Path) -> String {
+    let links = categories
+        .iter()
+        .filter(|category| !super::category_slug(category).is_empty())
+        .map(|category| {
+            format!(
+                "<a href=\"{}\">{}</a>",
+                project_root
+                    .join(super::category_path(category))
+                    .to_string_lossy(),
+                escape_html(category)
+            )
+        })
+        .collect::<Vec<_>>();
+    if links.is_empty() {
+        return String::new();
+    }
+    format!("<p class=\"categories\">{}</p>", links.join(", "))
+}
+
 fn join_iter(iter: impl Iterator<Item = String>, intersperse: &str) -> String {
     Itertools::intersperse(iter, intersperse.to_owned()).collect::<String>()
 }
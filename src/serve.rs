@@ -0,0 +1,148 @@
+use anyhow::Context;
+use oreneo::escape::percent_decode;
+use oreneo::page::LIVE_RELOAD_PATH;
+use oreneo::site::Site;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Browsers, listening for reload events
+type Clients = Arc<Mutex<Vec<TcpStream>>>;
+
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("json") => "application/json",
+        Some("xml") => "application/xml",
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("ico") => "image/x-icon",
+        Some("woff2") => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Map request path to a file in output directory. Returns [None] for paths escaping it
+fn resolve(output_root: &Path, url: &str) -> Option<PathBuf> {
+    let url = url.split(['?', '#']).next().unwrap_or_default();
+    let relative = PathBuf::from(percent_decode(url.trim_start_matches('/')));
+    if relative
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+
+    let path = output_root.join(relative);
+    if path.is_dir() {
+        Some(path.join("index.html"))
+    } else if path.extension().is_none() && !path.exists() {
+        Some(path.with_extension("html"))
+    } else {
+        Some(path)
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8], head: bool) {
+    let header = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    let _ = stream.write_all(header.as_bytes());
+    if !head {
+        let _ = stream.write_all(body);
+    }
+}
+
+fn handle(mut stream: TcpStream, output_root: &Path, clients: &Clients) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let mut request = request.split_whitespace();
+    let (method, url) = (
+        request.next().unwrap_or_default(),
+        request.next().unwrap_or("/"),
+    );
+    if method != "GET" && method != "HEAD" {
+        respond(
+            &mut stream,
+            "405 Method Not Allowed",
+            "text/plain",
+            b"Method Not Allowed",
+            false,
+        );
+        return Ok(());
+    }
+
+    if url == LIVE_RELOAD_PATH {
+        stream.write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n",
+        )?;
+        clients.lock().unwrap().push(stream);
+        return Ok(());
+    }
+
+    let head = method == "HEAD";
+    match resolve(output_root, url).map(|path| (std::fs::read(&path), path)) {
+        Some((Ok(body), path)) => respond(&mut stream, "200 OK", content_type(&path), &body, head),
+        Some((Err(_), _)) => respond(
+            &mut stream,
+            "404 Not Found",
+            "text/plain",
+            b"Not Found",
+            head,
+        ),
+        None => respond(
+            &mut stream,
+            "403 Forbidden",
+            "text/plain",
+            b"Forbidden",
+            head,
+        ),
+    }
+    Ok(())
+}
+
+/// Tell every connected browser to reload the page
+fn reload(clients: &Clients) {
+    clients
+        .lock()
+        .unwrap()
+        .retain_mut(|client| client.write_all(b"data: reload\n\n").is_ok());
+}
+
+/// Build the site in development mode, serve output directory and reload pages in
+/// browser after each rebuild
+pub fn serve(site: &Site, address: &str) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(address).context(format!("Failed to listen on {address}"))?;
+    println!("Serving {:?} on http://{address}", site.output_root());
+
+    let clients = Clients::default();
+    let output_root = site.output_root().to_owned();
+    let server_clients = clients.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let output_root = output_root.clone();
+            let clients = server_clients.clone();
+            std::thread::spawn(move || {
+                if let Err(err) = handle(stream, &output_root, &clients) {
+                    eprintln!("Request error: {err}");
+                }
+            });
+        }
+    });
+
+    crate::watch::watch(site, || reload(&clients))
+}
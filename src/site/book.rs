@@ -0,0 +1,259 @@
+use super::{write_if_changed, Site, SiteError};
+use crate::page::metadata::Metadata;
+use crate::page::nav::NavItem;
+use crate::page::Page;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// List of book chapters in page directory. Book mode is enabled, if it exists
+pub const SUMMARY_FILE: &str = "SUMMARY.neo";
+
+/// Page with all chapters of the book in output root
+pub(super) const PRINT_FILE: &str = "print.html";
+
+/// Title and path of HTML file of a chapter, relative to output root
+type Chapter = (String, PathBuf);
+
+/// A book, from [SUMMARY_FILE]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(super) struct Book {
+    /// Book title, from `-- title`
+    pub title: Option<String>,
+    /// Parts and chapters
+    pub chapters: Vec<NavItem>,
+}
+
+impl Book {
+    /// Parse a summary. Lines, that are not listed here, are ignored:
+    /// - `-- title`, followed by the book title
+    /// - `# Part title` starts a part of the book
+    /// - `- >Chapter title>/chapter.neo>` is a chapter, indented by 2 spaces for subchapters
+    /// - `- Chapter title` is a chapter without a page
+    pub fn parse(source: &str) -> Self {
+        let chapter = regex!(r"^( *)- (?:>([^>]+)>([^>]+)>|(.+?))\s*$");
+        let mut book = Book::default();
+        let mut lines = source.lines();
+        let mut part: Option<NavItem> = None;
+        // Chapters, that are not finished yet: top-level chapter, its subchapter, ...
+        let mut stack: Vec<NavItem> = Vec::new();
+        let mut numbers: Vec<usize> = Vec::new();
+
+        fn finish_chapters(stack: &mut Vec<NavItem>, depth: usize, parent: &mut Vec<NavItem>) {
+            while stack.len() > depth {
+                let chapter = stack.pop().unwrap();
+                match stack.last_mut() {
+                    Some(outer) => outer.children.push(chapter),
+                    None => parent.push(chapter),
+                }
+            }
+        }
+
+        while let Some(line) = lines.next() {
+            if line.trim() == "-- title" {
+                book.title = lines
+                    .by_ref()
+                    .map(str::trim)
+                    .find(|line| !line.is_empty())
+                    .map(str::to_owned);
+            } else if let Some(title) = line.strip_prefix("# ") {
+                let target = part
+                    .as_mut()
+                    .map_or(&mut book.chapters, |part| &mut part.children);
+                finish_chapters(&mut stack, 0, target);
+                book.chapters.extend(part.take());
+                part = Some(NavItem {
+                    title: title.trim().to_owned(),
+                    number: None,
+                    path: None,
+                    children: Vec::new(),
+                });
+            } else if let Some(captures) = chapter.captures(line) {
+                let depth = (captures[1].len() / 2).min(stack.len());
+                let target = part
+                    .as_mut()
+                    .map_or(&mut book.chapters, |part| &mut part.children);
+                finish_chapters(&mut stack, depth, target);
+                numbers.truncate(depth + 1);
+                numbers.resize(depth + 1, 0);
+                numbers[depth] += 1;
+                let (title, path) = match (captures.get(2), captures.get(3)) {
+                    (Some(title), Some(path)) => (
+                        title.as_str().trim(),
+                        Some(Path::new(path.as_str().trim().trim_start_matches('/'))),
+                    ),
+                    _ => (captures[4].trim(), None),
+                };
+                stack.push(NavItem {
+                    title: title.to_owned(),
+                    number: Some(numbers.iter().map(|number| format!("{number}.")).collect()),
+                    path: path.map(|path| path.with_extension("html")),
+                    children: Vec::new(),
+                });
+            }
+        }
+        let target = part
+            .as_mut()
+            .map_or(&mut book.chapters, |part| &mut part.children);
+        finish_chapters(&mut stack, 0, target);
+        book.chapters.extend(part);
+        book
+    }
+
+    /// Chapters with pages in reading order, see [chapter_pages]
+    pub fn pages(&self) -> Vec<Chapter> {
+        chapter_pages(&self.chapters)
+    }
+}
+
+/// Chapters with pages in reading order: title and path of HTML file.
+/// Items without chapter number are skipped, but not their children
+pub(super) fn chapter_pages(items: &[NavItem]) -> Vec<Chapter> {
+    fn collect(items: &[NavItem], pages: &mut Vec<Chapter>) {
+        for item in items {
+            if let (Some(_), Some(path)) = (&item.number, &item.path) {
+                pages.push((item.title.clone(), path.clone()));
+            }
+            collect(&item.children, pages);
+        }
+    }
+    let mut pages = Vec::new();
+    collect(items, &mut pages);
+    pages
+}
+
+/// Previous and next chapter of a page in book mode. Path is relative to page directory
+pub(super) fn pager(nav: &[NavItem], page_path: &Path) -> (Option<Chapter>, Option<Chapter>) {
+    let pages = chapter_pages(nav);
+    let html_path = page_path.with_extension("html");
+    let Some(index) = pages.iter().position(|(_, path)| *path == html_path) else {
+        return (None, None);
+    };
+    let prev = index.checked_sub(1).map(|index| pages[index].clone());
+    (prev, pages.get(index + 1).cloned())
+}
+
+impl Site {
+    /// Is book mode enabled: [SUMMARY_FILE] exists
+    pub(super) fn is_book(&self) -> bool {
+        self.page_root.join(SUMMARY_FILE).is_file()
+    }
+
+    /// The book, if [SUMMARY_FILE] exists
+    pub(super) fn book(&self) -> Option<Book> {
+        let source = std::fs::read_to_string(self.page_root.join(SUMMARY_FILE)).ok()?;
+        Some(Book::parse(&source))
+    }
+
+    /// Write a page with all published chapters of the book, for printing.
+    /// Returns written paths
+    pub(super) fn write_print_page(&self) -> Result<BTreeSet<PathBuf>, SiteError> {
+        let mut written = BTreeSet::new();
+        let Some(book) = self.book() else {
+            return Ok(written);
+        };
+        let mut pages = Vec::new();
+        for (_, path) in book.pages() {
+            let page_path = path.with_extension("neo");
+            if !self.page_root.join(&page_path).is_file() {
+                continue;
+            }
+            let page = self.load_page(&page_path)?;
+            if page.is_published(&self.options) {
+                let project_root = super::project_root(&page_path)?;
+                pages.push((page_path, project_root, page));
+            }
+        }
+
+        let mut metadata = Metadata {
+            title: book.title.clone().or_else(|| self.config.title.clone()),
+            ..Metadata::default()
+        };
+        metadata
+            .extra
+            .insert(String::from("noindex"), String::from("true"));
+        let page_path = Path::new(PRINT_FILE).with_extension("neo");
+        let options = self.page_options(&page_path);
+        let html = Page::combine(&pages, metadata, &page_path, &options)
+            .and_then(|page| page.to_html_string_with(Path::new(""), &options))
+            .map_err(|err| SiteError::Build(page_path, err))?;
+        let html_path = self.output_root.join(PRINT_FILE);
+        write_if_changed(&html_path, html.as_bytes())
+            .map_err(|err| SiteError::Write(html_path, err))?;
+        written.insert(PathBuf::from(PRINT_FILE));
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(title: &str, number: Option<&str>, path: Option<&str>) -> NavItem {
+        NavItem {
+            title: title.to_owned(),
+            number: number.map(str::to_owned),
+            path: path.map(PathBuf::from),
+            children: Vec::new(),
+        }
+    }
+
+    fn with_children(item: NavItem, children: Vec<NavItem>) -> NavItem {
+        NavItem { children, ..item }
+    }
+
+    #[test]
+    fn chapters_and_parts() {
+        // Chapters are numbered through parts
+        let book = Book::parse(
+            "-- title\n\n  My book  \n\n\
+             - >Intro>/intro.neo>\n\
+             # Guide\n\
+             - >Start>/guide/start.neo>\n\
+             \x20 - >Setup>/guide/setup.neo>\n\
+             \x20   - Draft chapter\n\
+             - >End>end.neo>\n\
+             Ignored line\n",
+        );
+        assert_eq!(book.title.as_deref(), Some("My book"));
+        assert_eq!(
+            book.chapters,
+            vec![
+                item("Intro", Some("1."), Some("intro.html")),
+                with_children(
+                    item("Guide", None, None),
+                    vec![
+                        with_children(
+                            item("Start", Some("2."), Some("guide/start.html")),
+                            vec![with_children(
+                                item("Setup", Some("2.1."), Some("guide/setup.html")),
+                                vec![item("Draft chapter", Some("2.1.1."), None)]
+                            )]
+                        ),
+                        item("End", Some("3."), Some("end.html")),
+                    ]
+                ),
+            ]
+        );
+        assert_eq!(
+            book.pages(),
+            vec![
+                (String::from("Intro"), PathBuf::from("intro.html")),
+                (String::from("Start"), PathBuf::from("guide/start.html")),
+                (String::from("Setup"), PathBuf::from("guide/setup.html")),
+                (String::from("End"), PathBuf::from("end.html")),
+            ]
+        );
+    }
+
+    #[test]
+    fn over_indented_chapters() {
+        let book = Book::parse("      - >Deep>deep.neo>\n- >Next>next.neo>\n");
+        assert_eq!(
+            book.chapters,
+            vec![
+                item("Deep", Some("1."), Some("deep.html")),
+                item("Next", Some("2."), Some("next.html")),
+            ]
+        );
+    }
+}
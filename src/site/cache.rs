@@ -0,0 +1,173 @@
+use crate::page::metadata::Metadata;
+use crate::page::Page;
+use crate::theme::THEMES;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Name of the cache file, placed into output directory
+pub const CACHE_FILE: &str = ".oreneo-cache";
+
+/// Bump when the cache format changes, so that old caches are discarded
+const CACHE_VERSION: u32 = 4;
+
+/// Hex-encoded SHA-256 of some data
+pub fn hash(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Hash of the renderer: crate version and bundled themes, that pages are rendered with.
+/// Computed once
+pub fn renderer_digest() -> &'static str {
+    static DIGEST: OnceLock<String> = OnceLock::new();
+    DIGEST.get_or_init(|| {
+        let mut data = String::from(env!("CARGO_PKG_VERSION"));
+        for theme in THEMES {
+            data.push_str(theme.name);
+            data.push_str(theme.layout);
+            for asset in theme.stylesheets.iter().chain(theme.scripts) {
+                data.push_str(asset.name);
+                data.push_str(asset.content);
+            }
+        }
+        hash(data.as_bytes())
+    })
+}
+
+/// What category pages, feeds and the sitemap need to know about a page,
+/// so that unchanged pages are not parsed again
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PageSummary {
+    /// Hash of page source
+    pub source: String,
+    /// Page metadata with defaults
+    pub metadata: Metadata,
+    /// See [Page::categories]
+    pub categories: Vec<String>,
+}
+
+impl PageSummary {
+    /// Summary of a page, loaded from source with this hash
+    pub fn new(source: String, page: &Page) -> Self {
+        Self {
+            source,
+            metadata: page.metadata().clone(),
+            categories: page.categories(),
+        }
+    }
+}
+
+/// Cached information about a built page
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    /// Hash of page source, renderer version and build options
+    pub key: String,
+    /// Files this page depends on (relative to page directory) with hashes of their content
+    pub dependencies: BTreeMap<PathBuf, String>,
+    /// Summary of the page, as it was built
+    pub summary: PageSummary,
+}
+
+impl CacheEntry {
+    /// Do all dependencies still have their recorded hashes
+    fn dependencies_unchanged(&self, mut hash_dependency: impl FnMut(&Path) -> String) -> bool {
+        self.dependencies
+            .iter()
+            .all(|(path, hash)| &hash_dependency(path) == hash)
+    }
+}
+
+/// Persistent cache of built pages, used to skip pages that didn't change
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BuildCache {
+    version: u32,
+    renderer: String,
+    pages: BTreeMap<PathBuf, CacheEntry>,
+    /// Files, generated from all pages, like category pages and feeds. Relative to output root
+    generated: BTreeSet<PathBuf>,
+}
+
+impl Default for BuildCache {
+    fn default() -> Self {
+        Self {
+            version: CACHE_VERSION,
+            renderer: env!("CARGO_PKG_VERSION").to_owned(),
+            pages: BTreeMap::new(),
+            generated: BTreeSet::new(),
+        }
+    }
+}
+
+impl BuildCache {
+    /// Load cache from a file. Missing, broken or outdated cache is treated as empty
+    pub fn load(path: &Path) -> Self {
+        std::fs::read(path)
+            .ok()
+            .and_then(|data| serde_json::from_slice::<Self>(&data).ok())
+            .filter(|cache| {
+                cache.version == CACHE_VERSION && cache.renderer == env!("CARGO_PKG_VERSION")
+            })
+            .unwrap_or_default()
+    }
+
+    /// Save cache to a file
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, serde_json::to_vec(self)?)
+    }
+
+    /// Check if a page with this key is in the cache and none of its dependencies changed.
+    /// `hash_dependency` should return current hash of a dependency, empty if it doesn't exist
+    pub fn is_fresh(
+        &self,
+        page_path: &Path,
+        key: &str,
+        mut hash_dependency: impl FnMut(&Path) -> String,
+    ) -> bool {
+        self.pages.get(page_path).is_some_and(|entry| {
+            entry.key == key && entry.dependencies_unchanged(&mut hash_dependency)
+        })
+    }
+
+    /// Summary of a page, if its source has this hash and none of its dependencies changed
+    pub fn summary(
+        &self,
+        page_path: &Path,
+        source: &str,
+        hash_dependency: impl FnMut(&Path) -> String,
+    ) -> Option<&PageSummary> {
+        self.pages
+            .get(page_path)
+            .filter(|entry| {
+                entry.summary.source == source && entry.dependencies_unchanged(hash_dependency)
+            })
+            .map(|entry| &entry.summary)
+    }
+
+    /// Record a built page
+    pub fn insert(&mut self, page_path: PathBuf, entry: CacheEntry) {
+        self.pages.insert(page_path, entry);
+    }
+
+    /// Files, generated from all pages, relative to output root
+    pub fn generated(&self) -> &BTreeSet<PathBuf> {
+        &self.generated
+    }
+
+    /// Record files, generated from all pages. Returns previously generated files,
+    /// that are not generated anymore
+    pub fn replace_generated(&mut self, generated: BTreeSet<PathBuf>) -> Vec<PathBuf> {
+        let stale = self.generated.difference(&generated).cloned().collect();
+        self.generated = generated;
+        stale
+    }
+
+    /// Forget about all pages, that are not in the list
+    pub fn retain(&mut self, pages: &BTreeSet<PathBuf>) {
+        self.pages.retain(|path, _| pages.contains(path));
+    }
+}
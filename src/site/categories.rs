@@ -0,0 +1,177 @@
+use super::cache::PageSummary;
+use super::{write_if_changed, Site, SiteError};
+use crate::page::metadata::Metadata;
+use crate::page::{category_path, category_slug, slugify, Page, CATEGORIES_DIR};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// Escape text, so that it's not formatted in a neopolitan page
+pub(super) fn escape_neo(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '<' | '>' | '*' | '_' | '~' | '`' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Quote text as a metadata value
+pub(super) fn quote_neo(text: &str) -> String {
+    format!(
+        "\"{}\"",
+        text.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+/// Link to a page in neopolitan, relative to project root
+pub(super) fn link_neo(text: &str, path: &Path) -> String {
+    let path = path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    format!(">{}>/{}>", escape_neo(text), path)
+}
+
+/// Title of a page for listings: title from metadata or file name
+pub(super) fn page_title(page_path: &Path, metadata: &Metadata) -> String {
+    metadata.title.clone().unwrap_or_else(|| {
+        page_path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    })
+}
+
+/// Names of a category and its parents, parents first: "rust", "rust/async"
+pub(super) fn category_names(category: &str) -> Vec<String> {
+    let parts = category
+        .split('/')
+        .map(str::trim)
+        .filter(|part| !slugify(part).is_empty())
+        .collect::<Vec<_>>();
+    (1..=parts.len())
+        .map(|depth| parts[..depth].join("/"))
+        .collect()
+}
+
+struct Category {
+    name: String,
+    pages: Vec<(String, PathBuf)>,
+}
+
+impl Site {
+    /// Write a page for every category and an index of all categories with page counts.
+    /// Pages of "rust/async" are listed in "rust" too. Returns written paths
+    pub(super) fn write_categories(
+        &self,
+        pages: &[(PathBuf, PageSummary)],
+    ) -> Result<BTreeSet<PathBuf>, SiteError> {
+        let mut categories = BTreeMap::<String, Category>::new();
+        for (page_path, page) in pages {
+            let title = page_title(page_path, &page.metadata);
+            for category in &page.categories {
+                for name in category_names(category) {
+                    let entry =
+                        categories
+                            .entry(category_slug(&name))
+                            .or_insert_with(|| Category {
+                                name,
+                                pages: Vec::new(),
+                            });
+                    let html_path = page_path.with_extension("html");
+                    if !entry.pages.iter().any(|(_, path)| *path == html_path) {
+                        entry.pages.push((title.clone(), html_path));
+                    }
+                }
+            }
+        }
+
+        let mut written = BTreeSet::new();
+        if !categories.is_empty() {
+            let mut index = String::from("-- metadata\n-- title: Categories\n\n");
+            index.push_str("-- title\nCategories\n\n-- list\n");
+            for category in categories.values() {
+                index.push_str(&format!(
+                    "- {} ({})\n",
+                    link_neo(&category.name, &category_path(&category.name)),
+                    category.pages.len()
+                ));
+            }
+            let index_path = Path::new(CATEGORIES_DIR).join("index.html");
+            self.write_generated(&index_path, &index)?;
+            written.insert(index_path);
+        }
+
+        for (slug, category) in &categories {
+            let title = format!("Category: {}", category.name);
+            let mut source = format!(
+                "-- metadata\n-- title: {}\n\n-- title\n{}\n\n",
+                quote_neo(&title),
+                escape_neo(&title)
+            );
+            let prefix = format!("{slug}/");
+            let subcategories = categories
+                .iter()
+                .filter(|(other, _)| {
+                    other
+                        .strip_prefix(&prefix)
+                        .is_some_and(|rest| !rest.contains('/'))
+                })
+                .collect::<Vec<_>>();
+            if !subcategories.is_empty() {
+                source.push_str("-- list\n-- title: Subcategories\n");
+                for (_, subcategory) in subcategories {
+                    source.push_str(&format!(
+                        "- {} ({})\n",
+                        link_neo(&subcategory.name, &category_path(&subcategory.name)),
+                        subcategory.pages.len()
+                    ));
+                }
+                source.push('\n');
+            }
+            source.push_str("-- list\n");
+            for (title, path) in &category.pages {
+                source.push_str(&format!("- {}\n", link_neo(title, path)));
+            }
+            source.push_str(&format!(
+                "\n-- p\n{}\n",
+                link_neo(
+                    "All categories",
+                    &Path::new(CATEGORIES_DIR).join("index.html")
+                )
+            ));
+
+            let path = category_path(&category.name);
+            self.write_generated(&path, &source)?;
+            written.insert(path);
+        }
+
+        Ok(written)
+    }
+
+    /// Render a generated neopolitan page into output. Path is relative to output root
+    pub(super) fn write_generated(&self, path: &Path, source: &str) -> Result<(), SiteError> {
+        let page_path = path.with_extension("neo");
+        let page =
+            Page::from_source(source).map_err(|err| SiteError::Parse(page_path.clone(), err))?;
+        let html = page
+            .to_html_string_with(
+                &super::project_root(&page_path)?,
+                &self.page_options(&page_path),
+            )
+            .map_err(|err| SiteError::Build(page_path, err))?;
+        let html_path = self.output_root.join(path);
+        write_if_changed(&html_path, html.as_bytes())
+            .map_err(|err| SiteError::Write(html_path, err))
+    }
+}
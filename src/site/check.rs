@@ -0,0 +1,294 @@
+use super::cache::BuildCache;
+use super::{Site, SiteError};
+use crate::escape::percent_decode;
+use crate::page::combine::{is_external_link, normalize_path};
+use rayon::prelude::*;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// A local link in generated HTML of a page, that points nowhere
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BrokenLink {
+    /// Where the link is
+    pub source: LinkSource,
+    /// Line in page source, where the link is written.
+    /// [None] for links from the layout and from generated files
+    pub line: Option<usize>,
+    /// Link, as written in generated HTML
+    pub link: String,
+    /// What the link points to
+    pub target: LinkTarget,
+}
+
+/// File with a broken link
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LinkSource {
+    /// Page, relative to page directory
+    Page(PathBuf),
+    /// File, generated from all pages, like `print.html` or category pages.
+    /// Relative to output directory
+    Generated(PathBuf),
+}
+
+/// Missing target of a broken link
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LinkTarget {
+    /// File, relative to output directory
+    File(PathBuf),
+    /// Element id in an HTML file, relative to output directory
+    Id(PathBuf, String),
+    /// Path outside of output directory
+    Outside,
+}
+
+impl std::fmt::Display for LinkTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::File(path) => write!(f, "no file {path:?}"),
+            Self::Id(path, id) => write!(f, "no element with id '{id}' in {path:?}"),
+            Self::Outside => write!(f, "points outside of output directory"),
+        }
+    }
+}
+
+fn decode_html(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// A local link, found in generated HTML
+struct Link {
+    link: String,
+    /// Linked file, relative to output directory. [None], if it is outside of it
+    path: Option<PathBuf>,
+    fragment: Option<String>,
+}
+
+impl Site {
+    /// Check that local links, image sources and `#fragment`s in generated HTML of all
+    /// published pages and of generated files, like `print.html` or category pages, point
+    /// to existing files and element ids in output directory. The site should be built first
+    pub fn check(&self) -> Result<Vec<BrokenLink>, SiteError> {
+        let attribute = regex!(r#"\s(?:href|src)\s*=\s*"([^"]*)""#);
+        let generated = BuildCache::load(&self.cache_path())
+            .generated()
+            .iter()
+            .filter(|path| path.extension().is_some_and(|ext| ext == "html"))
+            .map(|path| {
+                let html_path = self.output_root.join(path);
+                (LinkSource::Generated(path.clone()), html_path)
+            })
+            .collect::<Vec<_>>();
+        let sources = self
+            .published_pages()?
+            .into_iter()
+            .map(|(page_path, _)| {
+                let html_path = self.output_path(&page_path);
+                (LinkSource::Page(page_path), html_path)
+            })
+            .chain(generated)
+            .collect::<Vec<_>>();
+        let pages = sources
+            .into_par_iter()
+            .map(|(source, html_path)| {
+                let html = std::fs::read_to_string(&html_path)
+                    .map_err(|err| SiteError::Read(html_path.clone(), err))?;
+                let html_path = html_path
+                    .strip_prefix(&self.output_root)
+                    .unwrap_or(&html_path)
+                    .to_owned();
+                let links = attribute
+                    .captures_iter(&html)
+                    .map(|captures| decode_html(&captures[1]))
+                    .filter(|link| !link.is_empty() && !is_external_link(link))
+                    .map(|link| self.resolve(&html_path, link))
+                    .collect::<Vec<_>>();
+                Ok((source, links))
+            })
+            .collect::<Result<Vec<_>, SiteError>>()?;
+
+        let linked_html = pages
+            .iter()
+            .flat_map(|(_, links)| links)
+            .filter(|link| link.fragment.is_some())
+            .filter_map(|link| link.path.clone())
+            .collect::<BTreeSet<_>>();
+        let ids = linked_html
+            .into_par_iter()
+            .map(|path| {
+                let ids = self.element_ids(&path);
+                (path, ids)
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut broken = Vec::new();
+        for (source, links) in pages {
+            let mut source_lines = None;
+            let mut seen = HashMap::new();
+            for link in links {
+                let occurrence = seen.entry(link.link.clone()).or_insert(0);
+                *occurrence += 1;
+                let Some(target) = self.missing_target(&link, &ids) else {
+                    continue;
+                };
+                let line = match &source {
+                    LinkSource::Page(page_path) => {
+                        let lines = source_lines.get_or_insert_with(|| {
+                            std::fs::read_to_string(self.page_root.join(page_path))
+                                .unwrap_or_default()
+                        });
+                        source_line(lines, &link, *occurrence)
+                    }
+                    LinkSource::Generated(_) => None,
+                };
+                broken.push(BrokenLink {
+                    source: source.clone(),
+                    line,
+                    link: link.link,
+                    target,
+                });
+            }
+        }
+        Ok(broken)
+    }
+
+    /// Ids of all elements in an HTML file in output directory
+    fn element_ids(&self, path: &Path) -> Option<HashSet<String>> {
+        let id = regex!(r#"\sid\s*=\s*"([^"]*)""#);
+        let html = std::fs::read_to_string(self.output_root.join(path)).ok()?;
+        Some(
+            id.captures_iter(&html)
+                .map(|captures| decode_html(&captures[1]))
+                .collect(),
+        )
+    }
+
+    /// What a link points to, if it doesn't exist
+    fn missing_target(
+        &self,
+        link: &Link,
+        ids: &HashMap<PathBuf, Option<HashSet<String>>>,
+    ) -> Option<LinkTarget> {
+        let Some(path) = &link.path else {
+            return Some(LinkTarget::Outside);
+        };
+        if !self.output_root.join(path).is_file() {
+            return Some(LinkTarget::File(path.clone()));
+        }
+        // Empty fragment and `#top` link to the top of the page
+        let fragment = link.fragment.as_deref()?;
+        let is_html = matches!(
+            path.extension().and_then(|ext| ext.to_str()),
+            Some("html" | "htm")
+        );
+        if !is_html || fragment.is_empty() || fragment == "top" {
+            return None;
+        }
+        match ids.get(path) {
+            Some(Some(ids)) if ids.contains(fragment) => None,
+            _ => Some(LinkTarget::Id(path.clone(), fragment.to_owned())),
+        }
+    }
+
+    /// Resolve a link in an HTML file, with `%XX` escapes decoded.
+    /// Paths are relative to output directory.
+    /// Links to directories point to their `index.html`
+    fn resolve(&self, html_path: &Path, link: String) -> Link {
+        let (path, fragment) = match link.split_once('#') {
+            Some((path, fragment)) => (path, Some(percent_decode(fragment))),
+            None => (link.as_str(), None),
+        };
+        let path = percent_decode(path.split_once('?').map_or(path, |(path, _)| path));
+        let resolved = if path.is_empty() {
+            Some(html_path.to_owned())
+        } else if let Some(path) = path.strip_prefix('/') {
+            normalize_path(Path::new(path))
+        } else {
+            normalize_path(&html_path.parent().unwrap_or(Path::new("")).join(&path))
+        }
+        .map(|resolved| {
+            if path.ends_with('/') || self.output_root.join(&resolved).is_dir() {
+                resolved.join("index.html")
+            } else {
+                resolved
+            }
+        });
+        Link {
+            path: resolved,
+            fragment,
+            link,
+        }
+    }
+}
+
+/// Line of the link in page source, starting from 1. Links to local pages are usually
+/// written from project root, like `/dir/page.html`, but are relative in HTML.
+/// For repeated links, the line of the same occurrence is taken
+fn source_line(source: &str, link: &Link, occurrence: usize) -> Option<usize> {
+    let mut candidates = vec![link.link.clone()];
+    if let Some(path) = &link.path {
+        let mut absolute = format!("/{}", path.to_string_lossy());
+        if let Some(fragment) = &link.fragment {
+            absolute.push('#');
+            absolute.push_str(fragment);
+        }
+        candidates.push(absolute);
+    }
+    let lines = source
+        .lines()
+        .enumerate()
+        .flat_map(|(index, line)| {
+            let count = candidates
+                .iter()
+                .map(|candidate| line.matches(candidate.as_str()).count())
+                .max()
+                .unwrap_or(0);
+            std::iter::repeat_n(index + 1, count)
+        })
+        .collect::<Vec<_>>();
+    lines.get(occurrence - 1).or_else(|| lines.last()).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(link: &str) -> (Option<PathBuf>, Option<String>) {
+        let site = Site::new("page", "/nonexistent/output");
+        let link = site.resolve(Path::new("blog/post.html"), link.to_owned());
+        (link.path, link.fragment)
+    }
+
+    fn path(path: &str) -> Option<PathBuf> {
+        Some(PathBuf::from(path))
+    }
+
+    #[test]
+    fn relative_and_absolute_links() {
+        assert_eq!(resolve("other.html"), (path("blog/other.html"), None));
+        assert_eq!(resolve("./a/../b.html"), (path("blog/b.html"), None));
+        assert_eq!(resolve("../index.html"), (path("index.html"), None));
+        assert_eq!(resolve("/img/pic.png"), (path("img/pic.png"), None));
+        assert_eq!(resolve("../../outside.html"), (None, None));
+    }
+
+    #[test]
+    fn fragments_queries_and_directories() {
+        assert_eq!(
+            resolve("#intro"),
+            (path("blog/post.html"), Some(String::from("intro")))
+        );
+        assert_eq!(
+            resolve("other.html?page=2#top"),
+            (path("blog/other.html"), Some(String::from("top")))
+        );
+        assert_eq!(resolve("deep/"), (path("blog/deep/index.html"), None));
+        assert_eq!(
+            resolve("my%20page.html#caf%C3%A9"),
+            (path("blog/my page.html"), Some(String::from("café")))
+        );
+        assert_eq!(resolve("100%.html"), (path("blog/100%.html"), None));
+    }
+}
@@ -0,0 +1,342 @@
+use super::cache::PageSummary;
+use super::categories::{category_names, page_title};
+use super::{project_root, write_if_changed, Site, SiteError};
+use crate::escape::escape_attr;
+use crate::page::combine::{is_external_link, normalize_path, rewrite_attributes};
+use crate::page::metadata::Date;
+use crate::page::{category_path, category_slug, ATOM_FILE, CATEGORIES_DIR, FEED_FILE};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// Entries in a feed, if not configured
+const DEFAULT_LIMIT: usize = 20;
+
+/// Absolute URL of a path, relative to output root
+pub(super) fn absolute_url(base_url: &str, path: &Path) -> String {
+    let mut url = String::from(base_url);
+    for component in path.components() {
+        url.push('/');
+        url.push_str(&component.as_os_str().to_string_lossy());
+    }
+    url
+}
+
+/// Make relative `href`s and `src`s in HTML of a page absolute, like the print page rebases
+/// chapter links onto itself, so that they work in feed readers. Path of the page HTML
+/// is relative to output root
+fn absolute_links(html: &str, html_path: &Path, base_url: &str) -> String {
+    let page_dir = html_path.parent().unwrap_or(Path::new(""));
+    rewrite_attributes(html, |name, value| {
+        if name == "id" || value.is_empty() || value.starts_with('/') || is_external_link(value) {
+            return value.to_owned();
+        }
+        let (path, fragment) = match value.split_once('#') {
+            Some((path, fragment)) => (path, Some(fragment)),
+            None => (value, None),
+        };
+        let target = if path.is_empty() {
+            html_path.to_owned()
+        } else {
+            match normalize_path(&page_dir.join(path)) {
+                Some(target) => target,
+                None => return value.to_owned(),
+            }
+        };
+        let url = absolute_url(base_url, &target);
+        match fragment {
+            Some(fragment) => format!("{url}#{fragment}"),
+            None => url,
+        }
+    })
+}
+
+/// RSS wants an email in `<author>`, like `jane@example.com (Jane)`. Names go into
+/// `<dc:creator>`
+fn is_email(author: &str) -> bool {
+    author
+        .split_whitespace()
+        .next()
+        .is_some_and(|address| address.contains('@'))
+}
+
+/// A feed entry
+struct Entry {
+    /// Page of the entry, relative to page directory
+    page_path: PathBuf,
+    title: String,
+    url: String,
+    date: Date,
+    updated: Date,
+    author: Option<String>,
+    summary: Option<String>,
+    content: Option<String>,
+}
+
+/// A feed: site-wide or of a category
+struct Feed {
+    title: String,
+    /// Absolute URL of the page, that the feed is for
+    link: String,
+    /// Directory of feed files, relative to output root
+    dir: PathBuf,
+    /// Entries of the feed, as indexes into all entries
+    entries: Vec<usize>,
+}
+
+impl Site {
+    /// Write RSS (`feed.xml`) and Atom (`atom.xml`) feeds of pages with a date: one for the
+    /// whole site and one for every category in `categories/<slug>/`. Feeds require
+    /// base URL, otherwise nothing is written. With full content, only pages, that make it
+    /// into a feed, are loaded. Returns written paths
+    pub(super) fn write_feeds(
+        &self,
+        pages: &[(PathBuf, PageSummary)],
+    ) -> Result<BTreeSet<PathBuf>, SiteError> {
+        let mut written = BTreeSet::new();
+        let Some(base_url) = self
+            .config
+            .base_url
+            .as_deref()
+            .filter(|_| self.config.feeds())
+        else {
+            return Ok(written);
+        };
+        let base_url = base_url.trim_end_matches('/');
+
+        let mut entries = Vec::new();
+        for (page_path, page) in pages {
+            let metadata = &page.metadata;
+            let Some(date) = metadata.date else {
+                continue;
+            };
+            let entry = Entry {
+                page_path: page_path.clone(),
+                title: page_title(page_path, metadata),
+                url: self.page_options(page_path).url().unwrap_or_default(),
+                date,
+                updated: metadata.updated.unwrap_or(date),
+                author: metadata.author.clone(),
+                summary: metadata.description.clone(),
+                content: None,
+            };
+            entries.push((entry, &page.categories));
+        }
+        entries.sort_by(|(a, _), (b, _)| b.date.cmp(&a.date).then_with(|| a.url.cmp(&b.url)));
+
+        let site_title = self.config.title.clone().unwrap_or_default();
+        let mut feeds = vec![Feed {
+            title: site_title.clone(),
+            link: format!("{base_url}/"),
+            dir: PathBuf::new(),
+            entries: (0..entries.len()).collect(),
+        }];
+        let mut categories = BTreeMap::<String, Feed>::new();
+        for (index, (_, page_categories)) in entries.iter().enumerate() {
+            let mut slugs = BTreeSet::new();
+            for category in page_categories.iter() {
+                for name in category_names(category) {
+                    let slug = category_slug(&name);
+                    if !slugs.insert(slug.clone()) {
+                        continue;
+                    }
+                    let feed = categories.entry(slug.clone()).or_insert_with(|| Feed {
+                        title: format!("{site_title}: {name}")
+                            .trim_start_matches(": ")
+                            .to_owned(),
+                        link: absolute_url(base_url, &category_path(&name)),
+                        dir: Path::new(CATEGORIES_DIR).join(&slug),
+                        entries: Vec::new(),
+                    });
+                    feed.entries.push(index);
+                }
+            }
+        }
+        feeds.extend(categories.into_values());
+
+        let limit = self.config.feed.limit.unwrap_or(DEFAULT_LIMIT);
+        for feed in &mut feeds {
+            feed.entries.truncate(limit);
+        }
+        let mut entries = entries
+            .into_iter()
+            .map(|(entry, _)| entry)
+            .collect::<Vec<_>>();
+        if self.config.feed.full_content {
+            let used = feeds
+                .iter()
+                .flat_map(|feed| feed.entries.iter().copied())
+                .collect::<BTreeSet<_>>();
+            for index in used {
+                let entry = &mut entries[index];
+                entry.content = Some(self.feed_content(&entry.page_path, base_url)?);
+            }
+        }
+
+        for feed in feeds {
+            if feed.entries.is_empty() {
+                continue;
+            }
+            let feed_entries = feed
+                .entries
+                .iter()
+                .map(|&index| &entries[index])
+                .collect::<Vec<_>>();
+            for (file, xml) in [
+                (FEED_FILE, self.rss(&feed, &feed_entries, base_url)),
+                (ATOM_FILE, self.atom(&feed, &feed_entries, base_url)),
+            ] {
+                let path = feed.dir.join(file);
+                let full_path = self.output_root.join(&path);
+                write_if_changed(&full_path, xml.as_bytes())
+                    .map_err(|err| SiteError::Write(full_path, err))?;
+                written.insert(path);
+            }
+        }
+        Ok(written)
+    }
+
+    /// HTML of a page body with absolute links, for a feed with full content
+    fn feed_content(&self, page_path: &Path, base_url: &str) -> Result<String, SiteError> {
+        let page = self.load_page(page_path)?;
+        let html = page
+            .body_to_html(&project_root(page_path)?, &self.page_options(page_path))
+            .map_err(|err| SiteError::Build(page_path.to_owned(), err))?;
+        Ok(absolute_links(
+            &html,
+            &page_path.with_extension("html"),
+            base_url,
+        ))
+    }
+
+    fn rss(&self, feed: &Feed, entries: &[&Entry], base_url: &str) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(concat!(
+            "<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\" ",
+            "xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n"
+        ));
+        xml.push_str("<channel>\n");
+        xml.push_str(&format!("<title>{}</title>\n", escape_attr(&feed.title)));
+        xml.push_str(&format!("<link>{}</link>\n", escape_attr(&feed.link)));
+        xml.push_str(&format!(
+            "<atom:link href=\"{}\" rel=\"self\" type=\"application/rss+xml\"/>\n",
+            escape_attr(&absolute_url(base_url, &feed.dir.join(FEED_FILE)))
+        ));
+        xml.push_str(&format!(
+            "<description>{}</description>\n",
+            escape_attr(self.config.description.as_deref().unwrap_or(&feed.title))
+        ));
+        if let Some(lang) = &self.config.lang {
+            xml.push_str(&format!("<language>{}</language>\n", escape_attr(lang)));
+        }
+        if let Some(updated) = entries.iter().map(|entry| entry.updated).max() {
+            xml.push_str(&format!(
+                "<lastBuildDate>{}</lastBuildDate>\n",
+                updated.to_rfc2822()
+            ));
+        }
+        for entry in entries {
+            xml.push_str("<item>\n");
+            xml.push_str(&format!("<title>{}</title>\n", escape_attr(&entry.title)));
+            xml.push_str(&format!("<link>{}</link>\n", escape_attr(&entry.url)));
+            xml.push_str(&format!(
+                "<guid isPermaLink=\"true\">{}</guid>\n",
+                escape_attr(&entry.url)
+            ));
+            xml.push_str(&format!("<pubDate>{}</pubDate>\n", entry.date.to_rfc2822()));
+            match &entry.author {
+                Some(author) if is_email(author) => {
+                    xml.push_str(&format!("<author>{}</author>\n", escape_attr(author)));
+                }
+                Some(author) => {
+                    xml.push_str(&format!(
+                        "<dc:creator>{}</dc:creator>\n",
+                        escape_attr(author)
+                    ));
+                }
+                None => (),
+            }
+            if let Some(description) = entry.content.as_ref().or(entry.summary.as_ref()) {
+                xml.push_str(&format!(
+                    "<description>{}</description>\n",
+                    escape_attr(description)
+                ));
+            }
+            xml.push_str("</item>\n");
+        }
+        xml.push_str("</channel>\n</rss>\n");
+        xml
+    }
+
+    fn atom(&self, feed: &Feed, entries: &[&Entry], base_url: &str) -> String {
+        let self_url = absolute_url(base_url, &feed.dir.join(ATOM_FILE));
+        let updated = entries
+            .iter()
+            .map(|entry| entry.updated)
+            .max()
+            .map(|date| date.to_rfc3339())
+            .unwrap_or_default();
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<feed xmlns=\"http://www.w3.org/2005/Atom\" xml:base=\"{}/\"",
+            escape_attr(base_url)
+        ));
+        if let Some(lang) = &self.config.lang {
+            xml.push_str(&format!(" xml:lang=\"{}\"", escape_attr(lang)));
+        }
+        xml.push_str(">\n");
+        xml.push_str(&format!("<title>{}</title>\n", escape_attr(&feed.title)));
+        if let Some(description) = &self.config.description {
+            xml.push_str(&format!(
+                "<subtitle>{}</subtitle>\n",
+                escape_attr(description)
+            ));
+        }
+        xml.push_str(&format!("<id>{}</id>\n", escape_attr(&self_url)));
+        xml.push_str(&format!(
+            "<link href=\"{}\" rel=\"self\" type=\"application/atom+xml\"/>\n",
+            escape_attr(&self_url)
+        ));
+        xml.push_str(&format!("<updated>{updated}</updated>\n"));
+        if let Some(author) = &self.config.author {
+            xml.push_str(&format!(
+                "<author><name>{}</name></author>\n",
+                escape_attr(author)
+            ));
+        }
+        for entry in entries {
+            xml.push_str("<entry>\n");
+            xml.push_str(&format!("<title>{}</title>\n", escape_attr(&entry.title)));
+            xml.push_str(&format!(
+                "<link href=\"{}\" rel=\"alternate\" type=\"text/html\"/>\n",
+                escape_attr(&entry.url)
+            ));
+            xml.push_str(&format!("<id>{}</id>\n", escape_attr(&entry.url)));
+            xml.push_str(&format!(
+                "<published>{}</published>\n",
+                entry.date.to_rfc3339()
+            ));
+            xml.push_str(&format!(
+                "<updated>{}</updated>\n",
+                entry.updated.to_rfc3339()
+            ));
+            if let Some(author) = entry.author.as_ref().or(self.config.author.as_ref()) {
+                xml.push_str(&format!(
+                    "<author><name>{}</name></author>\n",
+                    escape_attr(author)
+                ));
+            }
+            if let Some(summary) = &entry.summary {
+                xml.push_str(&format!("<summary>{}</summary>\n", escape_attr(summary)));
+            }
+            if let Some(content) = &entry.content {
+                xml.push_str(&format!(
+                    "<content type=\"html\">{}</content>\n",
+                    escape_attr(content)
+                ));
+            }
+            xml.push_str("</entry>\n");
+        }
+        xml.push_str("</feed>\n");
+        xml
+    }
+}
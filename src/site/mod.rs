@@ -0,0 +1,558 @@
+use crate::config::Config;
+use crate::page::metadata::Metadata;
+use crate::page::{BuildOptions, Page, PageBuildError, PageParseError};
+use crate::theme::THEMES;
+pub use book::SUMMARY_FILE;
+use cache::{BuildCache, CacheEntry, PageSummary};
+pub use check::{BrokenLink, LinkSource, LinkTarget};
+use nav::Navigation;
+use rayon::prelude::*;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// Book mode, driven by the chapter list in `SUMMARY.neo`
+mod book;
+/// Build cache, used to skip pages that didn't change
+pub mod cache;
+/// Category pages, generated from categories of all pages
+mod categories;
+mod check;
+mod feeds;
+mod nav;
+mod sitemap;
+
+/// Directory of layout templates in page directory. Template `name` is `_templates/name.html`
+pub const TEMPLATES_DIR: &str = "_templates";
+
+/// Name of the file with metadata defaults for every page in its directory and subdirectories
+pub const DEFAULTS_FILE: &str = "_defaults.neo";
+
+fn is_page(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()) == Some("neo")
+        && !matches!(
+            path.file_name().and_then(|name| name.to_str()),
+            Some(DEFAULTS_FILE | SUMMARY_FILE)
+        )
+}
+
+/// Path of project root, relative to directory of a page
+fn project_root(page_path: &Path) -> Result<PathBuf, SiteError> {
+    page_path
+        .parent()
+        .and_then(|parent| pathdiff::diff_paths("", parent))
+        .ok_or_else(|| SiteError::RelativeRoot(page_path.to_owned()))
+}
+
+/// Write a file, creating parent directories. Files with the same content are not touched
+pub(crate) fn write_if_changed(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    if std::fs::read(path).ok().as_deref() == Some(content) {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)
+}
+
+/// A site. A directory of pages, that is built into a directory of HTML files
+#[derive(Clone, Debug)]
+pub struct Site {
+    page_root: PathBuf,
+    output_root: PathBuf,
+    config: Config,
+    options: BuildOptions,
+    /// Site navigation, see [Site::navigation]
+    nav: Arc<Mutex<Option<Navigation>>>,
+}
+
+impl Site {
+    /// Create a site from page directory and output directory
+    pub fn new<PP: Into<PathBuf>, OP: Into<PathBuf>>(page_root: PP, output_root: OP) -> Self {
+        Self {
+            page_root: page_root.into(),
+            output_root: output_root.into(),
+            config: Config::default(),
+            options: BuildOptions::default(),
+            nav: Arc::default(),
+        }
+    }
+
+    /// Use site config. Source and output directories of the config are ignored
+    pub fn with_config(self, config: Config) -> Self {
+        Self {
+            config,
+            nav: Arc::default(),
+            ..self
+        }
+    }
+
+    /// Use custom options to build pages. Page settings are taken from site config instead
+    pub fn with_options(self, options: BuildOptions) -> Self {
+        Self {
+            options,
+            nav: Arc::default(),
+            ..self
+        }
+    }
+
+    /// Site config
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Options, that every page is built with
+    pub fn options(&self) -> &BuildOptions {
+        &self.options
+    }
+
+    /// Options to build a page with. Path is relative to page directory
+    pub fn page_options(&self, page_path: &Path) -> BuildOptions {
+        let mut config = self.config.page_config(page_path);
+        if self.is_book() && config.theme.is_none() {
+            config.theme = Some(String::from("book"));
+        }
+        let nav = self.navigation();
+        let (prev, next) = book::pager(&nav, page_path);
+        BuildOptions {
+            config,
+            site_title: self.config.title.clone(),
+            base_url: self.config.base_url.clone(),
+            lang: self.config.lang.clone(),
+            path: Some(page_path.with_extension("html")),
+            feeds: self.config.feeds(),
+            nav,
+            prev,
+            next,
+            ..self.options.clone()
+        }
+    }
+
+    /// Page directory
+    pub fn page_root(&self) -> &Path {
+        &self.page_root
+    }
+
+    /// Output directory
+    pub fn output_root(&self) -> &Path {
+        &self.output_root
+    }
+
+    /// Path of the generated HTML file for a page. Path is relative to page directory
+    pub fn output_path(&self, page_path: &Path) -> PathBuf {
+        self.output_root.join(page_path.with_extension("html"))
+    }
+}
+
+impl Site {
+    /// Find all pages of the site. Paths are relative to page directory
+    pub fn pages(&self) -> Result<Vec<PathBuf>, SiteError> {
+        fn walk(page_root: &Path, path: &Path, pages: &mut Vec<PathBuf>) -> Result<(), SiteError> {
+            let dir = page_root.join(path);
+            for file in std::fs::read_dir(&dir)
+                .map_err(|err| SiteError::ReadDir(dir.clone(), err))?
+                .flatten()
+            {
+                let page_path = path.join(file.file_name());
+                if is_page(&page_path) {
+                    pages.push(page_path);
+                } else if page_root.join(&page_path).is_dir() {
+                    walk(page_root, &page_path, pages)?;
+                }
+            }
+            Ok(())
+        }
+
+        let mut pages = Vec::new();
+        walk(&self.page_root, Path::new(""), &mut pages)?;
+        pages.sort();
+        Ok(pages)
+    }
+
+    /// Files (other than the page itself), that a page depends on, like includes and templates.
+    /// Files may not exist yet, so that creating them rebuilds the page.
+    /// Paths are relative to page directory
+    pub fn dependencies(&self, page_path: &Path) -> Vec<PathBuf> {
+        match self.load_page(page_path) {
+            Ok(page) => self.page_dependencies(page_path, &page),
+            Err(_) => self.defaults_paths(page_path),
+        }
+    }
+
+    /// [Site::dependencies] of a loaded page
+    fn page_dependencies(&self, page_path: &Path, page: &Page) -> Vec<PathBuf> {
+        let mut dependencies = self.defaults_paths(page_path);
+        if let Ok(Some(name)) = page.template() {
+            dependencies.push(Path::new(TEMPLATES_DIR).join(format!("{name}.html")));
+        }
+        dependencies
+    }
+
+    /// Possible [DEFAULTS_FILE]s of a page, from page root to page directory
+    fn defaults_paths(&self, page_path: &Path) -> Vec<PathBuf> {
+        let mut paths = page_path
+            .ancestors()
+            .skip(1)
+            .map(|dir| dir.join(DEFAULTS_FILE))
+            .collect::<Vec<_>>();
+        paths.reverse();
+        paths
+    }
+
+    /// Metadata defaults of a page, from [DEFAULTS_FILE]s of its directory and parent directories.
+    /// Closer files override further ones
+    pub fn defaults(&self, page_path: &Path) -> Result<Metadata, SiteError> {
+        let mut defaults = Metadata::default();
+        for path in self.defaults_paths(page_path) {
+            if self.page_root.join(&path).is_file() {
+                let page = Page::load(self.page_root.join(&path))
+                    .map_err(|err| SiteError::Parse(path.clone(), err))?;
+                defaults.merge(page.metadata());
+            }
+        }
+        Ok(defaults)
+    }
+
+    /// Pages, that have to be rebuilt when the file at `path` changes.
+    /// Path is relative to page directory
+    pub fn dependents(&self, path: &Path) -> Vec<PathBuf> {
+        let mut dependents = Vec::new();
+        if is_page(path) {
+            dependents.push(path.to_owned());
+        }
+        for page_path in self.pages().unwrap_or_default() {
+            if self.dependencies(&page_path).iter().any(|dep| dep == path) {
+                dependents.push(page_path);
+            }
+        }
+        dependents
+    }
+
+    /// Path of the build cache
+    pub fn cache_path(&self) -> PathBuf {
+        self.output_root.join(cache::CACHE_FILE)
+    }
+
+    /// Cache key of a page: hash of its source, renderer version with bundled themes,
+    /// features and build options.
+    /// Navigation is hashed once per build, its digest stands for it
+    fn cache_key(&self, page_path: &Path, source: &str) -> String {
+        let options = BuildOptions {
+            nav: Arc::default(),
+            ..self.page_options(page_path)
+        };
+        let mut data = format!(
+            "{}\n{} {}\n{:?}\n{}\n",
+            cache::renderer_digest(),
+            crate::highlight::AVAILABLE,
+            crate::vendor::AVAILABLE,
+            options,
+            self.loaded_navigation().digest
+        )
+        .into_bytes();
+        data.extend(source.as_bytes());
+        cache::hash(&data)
+    }
+
+    fn hash_dependency(&self, path: &Path) -> String {
+        std::fs::read(self.page_root.join(path))
+            .map(|data| cache::hash(&data))
+            .unwrap_or_default()
+    }
+
+    /// Build every page of the site in parallel, skipping pages, that didn't change since the
+    /// last build. Pages, that failed to build, don't stop other pages from building
+    pub fn build(&self) -> Result<BuildReport, SiteError> {
+        let pages = self.pages()?;
+        self.refresh_navigation();
+        self.write_themes()?;
+        let cache_path = self.cache_path();
+        let mut cache = BuildCache::load(&cache_path);
+
+        let results = pages
+            .par_iter()
+            .map(|page_path| {
+                let source = self.read_page(page_path)?;
+                let key = self.cache_key(page_path, &source);
+                if self.output_path(page_path).exists()
+                    && cache.is_fresh(page_path, &key, |path| self.hash_dependency(path))
+                {
+                    return Ok(PageResult::Fresh);
+                }
+
+                let start = Instant::now();
+                let page = self.parse_page(page_path, &source)?;
+                let Some(dependencies) = self.write_page(page_path, &page)? else {
+                    return Ok(PageResult::Excluded);
+                };
+                let time = start.elapsed();
+                let dependencies = dependencies
+                    .into_iter()
+                    .map(|path| {
+                        let hash = self.hash_dependency(&path);
+                        (path, hash)
+                    })
+                    .collect();
+                let summary = PageSummary::new(cache::hash(source.as_bytes()), &page);
+                let entry = CacheEntry {
+                    key,
+                    dependencies,
+                    summary,
+                };
+                Ok(PageResult::Built(Box::new(entry), time))
+            })
+            .collect::<Vec<_>>();
+
+        let mut report = BuildReport::default();
+        let mut errors = Vec::new();
+        for (page_path, result) in pages.iter().zip(results) {
+            match result {
+                Ok(PageResult::Built(entry, time)) => {
+                    cache.insert(page_path.clone(), *entry);
+                    report.built.push((page_path.clone(), time));
+                }
+                Ok(PageResult::Fresh) => report.skipped.push(page_path.clone()),
+                Ok(PageResult::Excluded) => report.excluded.push(page_path.clone()),
+                Err(err) => errors.push(err),
+            }
+        }
+
+        cache.retain(&pages.into_iter().collect::<BTreeSet<_>>());
+        cache
+            .save(&cache_path)
+            .map_err(|err| SiteError::Write(cache_path, err))?;
+
+        if let Err(err) = self.write_indexes() {
+            errors.push(err);
+        }
+
+        match errors.len() {
+            0 => Ok(report),
+            1 => Err(errors.remove(0)),
+            _ => Err(SiteError::Multiple(errors)),
+        }
+    }
+
+    /// Write assets of bundled themes, highlighting stylesheets and third-party assets
+    /// into output directory
+    pub fn write_themes(&self) -> Result<(), SiteError> {
+        for theme in THEMES {
+            theme
+                .write(&self.output_root)
+                .map_err(|err| SiteError::Write(self.output_root.join(theme.dir()), err))?;
+        }
+
+        if self.config.local_assets() {
+            for asset in crate::vendor::ASSETS {
+                let path = self.output_root.join(asset.output_path());
+                write_if_changed(&path, asset.content)
+                    .map_err(|err| SiteError::Write(path, err))?;
+            }
+        }
+
+        for theme in crate::highlight::THEMES {
+            if let Some(stylesheet) = theme.stylesheet() {
+                let path = self.output_root.join(theme.stylesheet_path());
+                write_if_changed(&path, stylesheet.as_bytes())
+                    .map_err(|err| SiteError::Write(path, err))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove build cache, so that the next build rebuilds every page
+    pub fn clear_cache(&self) -> Result<(), SiteError> {
+        let cache_path = self.cache_path();
+        match std::fs::remove_file(&cache_path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                Err(SiteError::Write(cache_path, err))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Summaries of pages, that are built with current options. Summaries of pages,
+    /// that didn't change since the last build, come from the build cache, other pages
+    /// are parsed. Pages, that fail to load, are skipped
+    pub fn published_pages(&self) -> Result<Vec<(PathBuf, PageSummary)>, SiteError> {
+        let cache = BuildCache::load(&self.cache_path());
+        Ok(self
+            .pages()?
+            .into_par_iter()
+            .filter_map(|page_path| {
+                let summary = self.page_summary(&page_path, &cache).ok()?;
+                summary
+                    .metadata
+                    .is_published(&self.options)
+                    .then_some((page_path, summary))
+            })
+            .collect())
+    }
+
+    /// Summary of a page from the build cache, or from its source, if it changed
+    fn page_summary(&self, page_path: &Path, cache: &BuildCache) -> Result<PageSummary, SiteError> {
+        let source = self.read_page(page_path)?;
+        let hash = cache::hash(source.as_bytes());
+        if let Some(summary) = cache.summary(page_path, &hash, |path| self.hash_dependency(path)) {
+            return Ok(summary.clone());
+        }
+        let page = self.parse_page(page_path, &source)?;
+        Ok(PageSummary::new(hash, &page))
+    }
+
+    /// Write files, that are generated from all published pages: category pages, feeds,
+    /// sitemap, `robots.txt` and the print page of a book. Files, that were generated by
+    /// the previous build, but not by this one, are removed
+    pub fn write_indexes(&self) -> Result<(), SiteError> {
+        let pages = self.published_pages()?;
+        let mut written = self.write_categories(&pages)?;
+        written.extend(self.write_feeds(&pages)?);
+        written.extend(self.write_sitemap(&pages, &written)?);
+        written.extend(self.write_print_page()?);
+
+        let cache_path = self.cache_path();
+        let mut cache = BuildCache::load(&cache_path);
+        for file in cache.replace_generated(written) {
+            let path = self.output_root.join(file);
+            if path.exists() {
+                std::fs::remove_file(&path).map_err(|err| SiteError::Write(path, err))?;
+            }
+        }
+        cache
+            .save(&cache_path)
+            .map_err(|err| SiteError::Write(cache_path, err))
+    }
+
+    /// Load a page with its metadata defaults. Path is relative to page directory
+    pub fn load_page(&self, page_path: &Path) -> Result<Page, SiteError> {
+        self.parse_page(page_path, &self.read_page(page_path)?)
+    }
+
+    fn read_page(&self, page_path: &Path) -> Result<String, SiteError> {
+        std::fs::read_to_string(self.page_root.join(page_path))
+            .map_err(|err| SiteError::Parse(page_path.to_owned(), err.into()))
+    }
+
+    fn parse_page(&self, page_path: &Path, source: &str) -> Result<Page, SiteError> {
+        Ok(Page::from_source(source)
+            .map_err(|err| SiteError::Parse(page_path.to_owned(), err))?
+            .with_defaults(&self.defaults(page_path)?))
+    }
+
+    /// Build a single page. Path is relative to page directory.
+    /// Drafts and scheduled pages are not built and their old output is removed,
+    /// unless options allow them. Returns [Site::dependencies] of the page,
+    /// or [None] for such pages
+    pub fn build_page(&self, page_path: &Path) -> Result<Option<Vec<PathBuf>>, SiteError> {
+        self.write_page(page_path, &self.load_page(page_path)?)
+    }
+
+    /// [Site::build_page] with a loaded page
+    fn write_page(&self, page_path: &Path, page: &Page) -> Result<Option<Vec<PathBuf>>, SiteError> {
+        let options = self.page_options(page_path);
+        if !page.is_published(&options) {
+            self.remove_page(page_path)?;
+            return Ok(None);
+        }
+        let template = page
+            .template()
+            .map_err(|err| SiteError::Build(page_path.to_owned(), err))?;
+        let layout = match template {
+            Some(name) => {
+                let path = Path::new(TEMPLATES_DIR).join(format!("{name}.html"));
+                Some(
+                    std::fs::read_to_string(self.page_root.join(&path))
+                        .map_err(|err| SiteError::Template(path, err))?,
+                )
+            }
+            None => None,
+        };
+
+        let project_root = project_root(page_path)?;
+        let generated_html = page
+            .to_html_string_with_layout(&project_root, &options, layout.as_deref())
+            .map_err(|err| SiteError::Build(page_path.to_owned(), err))?;
+
+        let html_path = self.output_path(page_path);
+        if let Some(parent) = html_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|err| SiteError::Write(html_path.clone(), err))?;
+        }
+        std::fs::write(&html_path, generated_html)
+            .map_err(|err| SiteError::Write(html_path, err))?;
+        Ok(Some(self.page_dependencies(page_path, page)))
+    }
+
+    /// Remove generated HTML of a page, that no longer exists. Path is relative to page directory
+    pub fn remove_page(&self, page_path: &Path) -> Result<(), SiteError> {
+        let html_path = self.output_path(page_path);
+        match std::fs::remove_file(&html_path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                Err(SiteError::Write(html_path, err))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Result of a site build
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BuildReport {
+    /// Pages, that were built, with time it took to build each
+    pub built: Vec<(PathBuf, Duration)>,
+    /// Pages, that were up to date and skipped
+    pub skipped: Vec<PathBuf>,
+    /// Drafts and scheduled pages, that were not built
+    pub excluded: Vec<PathBuf>,
+}
+
+enum PageResult {
+    Fresh,
+    Built(Box<CacheEntry>, Duration),
+    Excluded,
+}
+
+// * ------------------------------------- Error ------------------------------------ * //
+/// An error occured while building a site
+#[derive(Error, Debug)]
+pub enum SiteError {
+    /// Failed to read page directory
+    #[error("Failed to read page dir {0:?}")]
+    ReadDir(PathBuf, #[source] std::io::Error),
+    /// Failed to parse page
+    #[error("Failed to parse page {0:?}!")]
+    Parse(PathBuf, #[source] PageParseError),
+    /// Failed to build page
+    #[error("Failed to build page {0:?}!")]
+    Build(PathBuf, #[source] PageBuildError),
+    /// Failed to read generated file
+    #[error("Failed to read generated file {0:?}!")]
+    Read(PathBuf, #[source] std::io::Error),
+    /// Failed to write generated file
+    #[error("Failed to write page {0:?}!")]
+    Write(PathBuf, #[source] std::io::Error),
+    /// Failed to find project root relative to page
+    #[error("Failed to construct relative path of project root for page {0:?}!")]
+    RelativeRoot(PathBuf),
+    /// Failed to read layout template
+    #[error("Failed to read template {0:?}!")]
+    Template(PathBuf, #[source] std::io::Error),
+    /// Multiple pages failed to build. Errors are in page order
+    #[error("{} pages failed to build:{}", .0.len(), format_errors(.0))]
+    Multiple(Vec<SiteError>),
+}
+
+fn format_errors(errors: &[SiteError]) -> String {
+    let mut text = String::new();
+    for err in errors {
+        text.push_str(&format!("\n{err}"));
+        let mut source = std::error::Error::source(err);
+        while let Some(err) = source {
+            text.push_str(&format!("\n    Caused by: {err}"));
+            source = err.source();
+        }
+    }
+    text
+}
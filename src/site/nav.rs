@@ -0,0 +1,157 @@
+use super::book::PRINT_FILE;
+use super::categories::page_title;
+use super::Site;
+use crate::page::nav::NavItem;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Ordering file in a directory, if not configured
+const DEFAULT_ORDER_FILE: &str = "_order.txt";
+
+/// Index page of a directory
+const INDEX_PAGE: &str = "index.neo";
+
+/// Loaded site navigation, shared by all pages
+#[derive(Clone, Debug, Default)]
+pub(super) struct Navigation {
+    pub items: Arc<Vec<NavItem>>,
+    /// Hash of the items, used in cache keys of pages instead of the whole tree
+    pub digest: String,
+}
+
+impl Navigation {
+    fn new(items: Vec<NavItem>) -> Self {
+        let digest = super::cache::hash(format!("{items:?}").as_bytes());
+        Self {
+            items: Arc::new(items),
+            digest,
+        }
+    }
+}
+
+impl Site {
+    /// Site navigation, loaded on first use: chapters in book mode, or the page tree.
+    /// Empty, if disabled in config
+    pub fn navigation(&self) -> Arc<Vec<NavItem>> {
+        self.loaded_navigation().items
+    }
+
+    /// Site navigation with its digest, loaded on first use
+    pub(super) fn loaded_navigation(&self) -> Navigation {
+        let mut cached = self.nav.lock().unwrap();
+        cached
+            .get_or_insert_with(|| Navigation::new(self.load_navigation()))
+            .clone()
+    }
+
+    /// Reload site navigation, after pages were added, removed or renamed.
+    /// Returns true, if it changed
+    pub fn refresh_navigation(&self) -> bool {
+        let nav = Navigation::new(self.load_navigation());
+        let mut cached = self.nav.lock().unwrap();
+        let changed = cached.as_ref().map(|cached| &cached.digest) != Some(&nav.digest);
+        *cached = Some(nav);
+        changed
+    }
+
+    fn load_navigation(&self) -> Vec<NavItem> {
+        if let Some(mut book) = self.book() {
+            book.chapters.push(NavItem {
+                title: String::from("Print"),
+                number: None,
+                path: Some(PathBuf::from(PRINT_FILE)),
+                children: Vec::new(),
+            });
+            return book.chapters;
+        }
+        if !self.config.nav.enabled {
+            return Vec::new();
+        }
+        let Ok(pages) = self.published_pages() else {
+            return Vec::new();
+        };
+        let titles = pages
+            .into_iter()
+            .map(|(page_path, page)| {
+                let title = page_title(&page_path, &page.metadata);
+                (page_path, title)
+            })
+            .collect::<BTreeMap<_, _>>();
+        self.nav_items(Path::new(""), &titles)
+    }
+
+    /// Items for pages and subdirectories of a directory. Directory index page is
+    /// the directory item itself, except in the root directory
+    fn nav_items(&self, dir: &Path, titles: &BTreeMap<PathBuf, String>) -> Vec<NavItem> {
+        let mut items = Vec::new();
+        let mut subdirs = BTreeSet::new();
+        for (page_path, title) in titles {
+            let Ok(rest) = page_path.strip_prefix(dir) else {
+                continue;
+            };
+            let mut components = rest.components();
+            let Some(first) = components.next() else {
+                continue;
+            };
+            if components.next().is_some() {
+                subdirs.insert(first.as_os_str().to_owned());
+            } else if dir.as_os_str().is_empty() || rest != Path::new(INDEX_PAGE) {
+                let name = page_path.file_stem().unwrap_or_default().to_owned();
+                let item = NavItem {
+                    title: title.clone(),
+                    number: None,
+                    path: Some(page_path.with_extension("html")),
+                    children: Vec::new(),
+                };
+                items.push((name, item));
+            }
+        }
+        for name in subdirs {
+            let subdir = dir.join(&name);
+            let index = subdir.join(INDEX_PAGE);
+            let item = NavItem {
+                title: titles
+                    .get(&index)
+                    .cloned()
+                    .unwrap_or_else(|| name.to_string_lossy().into_owned()),
+                number: None,
+                path: titles
+                    .contains_key(&index)
+                    .then(|| index.with_extension("html")),
+                children: self.nav_items(&subdir, titles),
+            };
+            items.push((name, item));
+        }
+
+        let order = self.nav_order(dir);
+        items.sort_by_cached_key(|(name, _)| {
+            let name = name.to_string_lossy().into_owned();
+            let position = order
+                .iter()
+                .position(|entry| {
+                    *entry == name || Path::new(entry).file_stem() == Some(name.as_ref())
+                })
+                .unwrap_or(order.len());
+            (position, name)
+        });
+        items.into_iter().map(|(_, item)| item).collect()
+    }
+
+    /// Names of pages and subdirectories from the ordering file of a directory
+    fn nav_order(&self, dir: &Path) -> Vec<String> {
+        let file = self
+            .config
+            .nav
+            .order_file
+            .as_deref()
+            .unwrap_or(DEFAULT_ORDER_FILE);
+        std::fs::read_to_string(self.page_root.join(dir).join(file))
+            .unwrap_or_default()
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_owned)
+            .collect()
+    }
+}
@@ -0,0 +1,98 @@
+use super::cache::PageSummary;
+use super::feeds::absolute_url;
+use super::{write_if_changed, Site, SiteError};
+use crate::escape::escape_attr;
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// Sitemap of the site in output root
+pub(super) const SITEMAP_FILE: &str = "sitemap.xml";
+
+/// Instructions for crawlers in output root
+pub(super) const ROBOTS_FILE: &str = "robots.txt";
+
+impl Site {
+    /// Write `sitemap.xml` with published pages and generated pages, and `robots.txt`.
+    /// Drafts, scheduled pages and pages with `-- noindex: true` are not listed.
+    /// Both files require base URL, otherwise nothing is written. Returns written paths
+    pub(super) fn write_sitemap(
+        &self,
+        pages: &[(PathBuf, PageSummary)],
+        generated: &BTreeSet<PathBuf>,
+    ) -> Result<BTreeSet<PathBuf>, SiteError> {
+        let mut written = BTreeSet::new();
+        let Some(base_url) = self.config.base_url.as_deref() else {
+            return Ok(written);
+        };
+        let base_url = base_url.trim_end_matches('/');
+
+        let mut urls = Vec::new();
+        for (page_path, page) in pages {
+            let metadata = &page.metadata;
+            if metadata.is_draft() || metadata.is_scheduled() || metadata.is_noindex() {
+                continue;
+            }
+            let lastmod = match metadata.updated.or(metadata.date) {
+                Some(date) => Some(date.to_rfc3339_opts(SecondsFormat::Secs, true)),
+                None => std::fs::metadata(self.page_root.join(page_path))
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+                    .map(|time| {
+                        DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Secs, true)
+                    }),
+            };
+            urls.push((page_path.with_extension("html"), lastmod));
+        }
+        urls.extend(
+            generated
+                .iter()
+                .filter(|path| path.extension().is_some_and(|ext| ext == "html"))
+                .map(|path| (path.clone(), None)),
+        );
+        urls.sort();
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+        for (path, lastmod) in urls {
+            xml.push_str("<url>\n");
+            xml.push_str(&format!(
+                "<loc>{}</loc>\n",
+                escape_attr(&absolute_url(base_url, &path))
+            ));
+            if let Some(lastmod) = lastmod {
+                xml.push_str(&format!("<lastmod>{lastmod}</lastmod>\n"));
+            }
+            xml.push_str("</url>\n");
+        }
+        xml.push_str("</urlset>\n");
+        self.write_root_file(SITEMAP_FILE, &xml)?;
+        written.insert(PathBuf::from(SITEMAP_FILE));
+
+        if self.config.robots.enabled.unwrap_or(true) {
+            let robots = &self.config.robots;
+            let mut text = String::from("User-agent: *\n");
+            for path in &robots.allow {
+                text.push_str(&format!("Allow: {path}\n"));
+            }
+            for path in &robots.disallow {
+                text.push_str(&format!("Disallow: {path}\n"));
+            }
+            if robots.allow.is_empty() && robots.disallow.is_empty() {
+                text.push_str("Allow: /\n");
+            }
+            text.push_str(&format!(
+                "\nSitemap: {}\n",
+                absolute_url(base_url, Path::new(SITEMAP_FILE))
+            ));
+            self.write_root_file(ROBOTS_FILE, &text)?;
+            written.insert(PathBuf::from(ROBOTS_FILE));
+        }
+        Ok(written)
+    }
+
+    fn write_root_file(&self, file: &str, content: &str) -> Result<(), SiteError> {
+        let path = self.output_root.join(file);
+        write_if_changed(&path, content.as_bytes()).map_err(|err| SiteError::Write(path, err))
+    }
+}
@@ -0,0 +1,218 @@
+use crate::escape::escape_attr;
+use std::collections::BTreeMap;
+
+/// A template variable
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Var {
+    /// Plain text, escaped when rendered
+    Text(String),
+    /// HTML, inserted as-is
+    Html(String),
+    /// A list of plain text items
+    List(Vec<String>),
+}
+
+impl Var {
+    fn is_truthy(&self) -> bool {
+        match self {
+            Var::Text(text) | Var::Html(text) => !text.is_empty(),
+            Var::List(list) => !list.is_empty(),
+        }
+    }
+}
+
+/// Variables, available in a template
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Context {
+    vars: BTreeMap<String, Var>,
+}
+
+impl Context {
+    /// Set a plain text variable. [None] leaves the variable unset
+    pub fn text(&mut self, name: impl Into<String>, value: Option<impl Into<String>>) {
+        if let Some(value) = value {
+            self.vars.insert(name.into(), Var::Text(value.into()));
+        }
+    }
+
+    /// Set an HTML variable
+    pub fn html(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.vars.insert(name.into(), Var::Html(value.into()));
+    }
+
+    /// Set a list variable
+    pub fn list(&mut self, name: impl Into<String>, items: Vec<String>) {
+        self.vars.insert(name.into(), Var::List(items));
+    }
+
+    /// Get a variable
+    pub fn get(&self, name: &str) -> Option<&Var> {
+        self.vars.get(name)
+    }
+}
+
+// * ------------------------------------- Parse ------------------------------------ * //
+#[derive(Debug)]
+enum Node<'a> {
+    Text(&'a str),
+    Var(&'a str),
+    If(&'a str, Vec<Node<'a>>, Vec<Node<'a>>),
+    Each(&'a str, Vec<Node<'a>>),
+}
+
+/// Nodes, nodes after `{{else}}` and the rest of the template
+type Parsed<'a> = (Vec<Node<'a>>, Option<Vec<Node<'a>>>, &'a str);
+
+/// Parse nodes until `{{/end}}` (or end of template, if `end` is [None])
+fn parse<'a>(mut source: &'a str, end: Option<&str>) -> Result<Parsed<'a>, String> {
+    let mut nodes = Vec::new();
+    let mut else_nodes: Option<Vec<Node>> = None;
+    loop {
+        let Some(start) = source.find("{{") else {
+            if let Some(end) = end {
+                return Err(format!("Missing {{{{/{end}}}}}"));
+            }
+            push(&mut nodes, &mut else_nodes, Node::Text(source));
+            return Ok((nodes, else_nodes, ""));
+        };
+        push(&mut nodes, &mut else_nodes, Node::Text(&source[..start]));
+        let tag_end = source[start..]
+            .find("}}")
+            .ok_or_else(|| format!("Unclosed tag '{}'", &source[start..]))?;
+        let tag = source[start + 2..start + tag_end].trim();
+        source = &source[start + tag_end + 2..];
+
+        if let Some(block) = tag.strip_prefix('#') {
+            let (kind, name) = block.split_once(' ').unwrap_or((block, ""));
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(format!("Missing variable name in '{{{{{tag}}}}}'"));
+            }
+            let (body, else_body, rest) = parse(source, Some(kind))?;
+            source = rest;
+            let node = match kind {
+                "if" => Node::If(name, body, else_body.unwrap_or_default()),
+                "each" if else_body.is_none() => Node::Each(name, body),
+                _ => return Err(format!("Unknown block '{{{{{tag}}}}}'")),
+            };
+            push(&mut nodes, &mut else_nodes, node);
+        } else if let Some(block) = tag.strip_prefix('/') {
+            return match end {
+                Some(end) if end == block => Ok((nodes, else_nodes, source)),
+                _ => Err(format!("Unexpected '{{{{{tag}}}}}'")),
+            };
+        } else if tag == "else" {
+            if end != Some("if") || else_nodes.is_some() {
+                return Err(String::from("Unexpected '{{else}}'"));
+            }
+            else_nodes = Some(Vec::new());
+        } else {
+            push(&mut nodes, &mut else_nodes, Node::Var(tag));
+        }
+    }
+}
+
+fn push<'a>(nodes: &mut Vec<Node<'a>>, else_nodes: &mut Option<Vec<Node<'a>>>, node: Node<'a>) {
+    else_nodes.as_mut().unwrap_or(nodes).push(node);
+}
+
+// * ------------------------------------ Render ------------------------------------ * //
+fn render_nodes(nodes: &[Node], context: &Context, this: Option<&str>, html: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => html.push_str(text),
+            Node::Var("this") => html.push_str(&escape_attr(this.unwrap_or_default())),
+            Node::Var(name) => match context.get(name) {
+                Some(Var::Text(text)) => html.push_str(&escape_attr(text)),
+                Some(Var::Html(code)) => html.push_str(code),
+                Some(Var::List(list)) => html.push_str(&escape_attr(&list.join(", "))),
+                None => (),
+            },
+            Node::If(name, body, else_body) => {
+                let truthy = match *name {
+                    "this" => this.is_some_and(|this| !this.is_empty()),
+                    name => context.get(name).is_some_and(Var::is_truthy),
+                };
+                let body = if truthy { body } else { else_body };
+                render_nodes(body, context, this, html);
+            }
+            Node::Each(name, body) => {
+                let items = match context.get(name) {
+                    Some(Var::List(list)) => list.as_slice(),
+                    Some(Var::Text(text)) => std::slice::from_ref(text),
+                    _ => &[],
+                };
+                for item in items {
+                    render_nodes(body, context, Some(item), html);
+                }
+            }
+        }
+    }
+}
+
+/// Render a template. Syntax:
+/// - `{{name}}` inserts a variable. Text is escaped, HTML is not. Unset variables are empty
+/// - `{{#if name}}...{{else}}...{{/if}}` renders a block if the variable is set and not empty
+/// - `{{#each name}}...{{this}}...{{/each}}` renders a block for every item of a list
+pub fn render(template: &str, context: &Context) -> Result<String, String> {
+    let (nodes, _, _) = parse(template, None)?;
+    let mut html = String::with_capacity(template.len());
+    render_nodes(&nodes, context, None, &mut html);
+    Ok(html)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> Context {
+        let mut context = Context::default();
+        context.text("title", Some("A & B"));
+        context.text("empty", Some(""));
+        context.html("body", "<p>Hi</p>");
+        context.list("tags", vec![String::from("<a>"), String::from("b")]);
+        context
+    }
+
+    #[test]
+    fn variables() {
+        assert_eq!(
+            render("{{ title }}|{{body}}|{{tags}}|{{missing}}", &context()),
+            Ok(String::from("A &amp; B|<p>Hi</p>|&lt;a&gt;, b|"))
+        );
+    }
+
+    #[test]
+    fn if_blocks() {
+        let template = "{{#if title}}T{{else}}F{{/if}}{{#if empty}}T{{else}}F{{/if}}\
+            {{#if missing}}T{{/if}}";
+        assert_eq!(render(template, &context()), Ok(String::from("TF")));
+    }
+
+    #[test]
+    fn nested_blocks() {
+        let template = "{{#each tags}}{{#if this}}[{{this}}{{#if title}}!{{/if}}]{{/if}}{{/each}}\
+            {{#if tags}}{{#each title}}({{this}}){{/each}}{{/if}}";
+        assert_eq!(
+            render(template, &context()),
+            Ok(String::from("[&lt;a&gt;!][b!](A &amp; B)"))
+        );
+    }
+
+    #[test]
+    fn broken_templates() {
+        for template in [
+            "{{#if title}}",
+            "{{title",
+            "{{/if}}",
+            "{{#if}}{{/if}}",
+            "{{#if title}}{{/each}}",
+            "{{#if title}}{{else}}{{else}}{{/if}}",
+            "{{#each tags}}{{else}}{{/each}}",
+            "{{else}}",
+            "{{#unless title}}{{/unless}}",
+        ] {
+            assert!(render(template, &context()).is_err(), "{template}");
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use crate::template::Context;
+use std::path::{Path, PathBuf};
+
+/// A file, bundled into the binary
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Asset {
+    /// File name
+    pub name: &'static str,
+    /// File content
+    pub content: &'static str,
+}
+
+macro_rules! asset {
+    ($dir: literal, $name: literal) => {
+        Asset {
+            name: $name,
+            content: include_str!(concat!($dir, "/", $name)),
+        }
+    };
+}
+
+/// A theme. Stylesheets, scripts and layout of a page
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Theme {
+    /// Theme name
+    pub name: &'static str,
+    /// Stylesheets, linked in `<head>`
+    pub stylesheets: &'static [Asset],
+    /// Scripts, linked in `<head>`
+    pub scripts: &'static [Asset],
+    /// Page layout, a [template](crate::template). `{{head}}` and `{{body}}` are replaced
+    /// with contents of `<head>` and `<body>`, `{{lang}}` with page language
+    pub layout: &'static str,
+}
+
+/// Name of the theme, used when none is selected
+pub const DEFAULT_THEME: &str = "default";
+
+/// Themes, bundled into the binary
+pub const THEMES: &[Theme] = &[
+    Theme {
+        name: "default",
+        stylesheets: &[asset!("default", "global.css")],
+        scripts: &[],
+        layout: include_str!("default/layout.html"),
+    },
+    Theme {
+        name: "light",
+        stylesheets: &[
+            asset!("default", "global.css"),
+            asset!("light", "light.css"),
+        ],
+        scripts: &[],
+        layout: include_str!("default/layout.html"),
+    },
+    Theme {
+        name: "book",
+        stylesheets: &[asset!("default", "global.css"), asset!("book", "book.css")],
+        scripts: &[],
+        layout: include_str!("book/layout.html"),
+    },
+];
+
+impl Theme {
+    /// Find a bundled theme by name
+    pub fn find(name: &str) -> Option<&'static Theme> {
+        THEMES.iter().find(|theme| theme.name == name)
+    }
+
+    /// Directory of theme assets in output, relative to output root
+    pub fn dir(&self) -> PathBuf {
+        Path::new("theme").join(self.name)
+    }
+
+    /// Paths of theme stylesheets, relative to output root
+    pub fn stylesheet_paths(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        self.stylesheets
+            .iter()
+            .map(|asset| self.dir().join(asset.name))
+    }
+
+    /// Paths of theme scripts, relative to output root
+    pub fn script_paths(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        self.scripts.iter().map(|asset| self.dir().join(asset.name))
+    }
+
+    /// Write theme assets into output directory. Files with the same content are not touched
+    pub fn write(&self, output_root: &Path) -> std::io::Result<()> {
+        let dir = output_root.join(self.dir());
+        for asset in self.stylesheets.iter().chain(self.scripts) {
+            crate::site::write_if_changed(&dir.join(asset.name), asset.content.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Render theme layout, see [crate::template::render]
+    pub fn render(&self, context: &Context) -> Result<String, String> {
+        crate::template::render(self.layout, context)
+    }
+}
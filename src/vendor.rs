@@ -0,0 +1,87 @@
+use std::path::{Path, PathBuf};
+
+/// CDN, that third-party assets are loaded from, when they are not written into output
+pub const CDN: &str = "https://cdnjs.cloudflare.com/ajax/libs";
+
+/// Is content of [ASSETS] compiled in
+pub const AVAILABLE: bool = cfg!(feature = "vendor");
+
+/// A third-party asset, loaded from [CDN] or bundled into the binary
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VendoredAsset {
+    /// Versioned path, both on the CDN and in output directory (under `vendor/`)
+    pub path: &'static str,
+    /// Pinned Subresource Integrity hash, like `sha384-...`. `vendor/fetch.sh` checks
+    /// downloaded files against it and prints hashes of unpinned ones
+    pub integrity: Option<&'static str>,
+    /// File content. Empty without `vendor` feature
+    pub content: &'static [u8],
+}
+
+#[cfg(feature = "vendor")]
+macro_rules! content {
+    ($path: literal) => {
+        include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/vendor/", $path))
+    };
+}
+
+#[cfg(not(feature = "vendor"))]
+macro_rules! content {
+    ($path: literal) => {
+        &[]
+    };
+}
+
+macro_rules! vendored {
+    ($path: literal, $integrity: expr) => {
+        VendoredAsset {
+            path: $path,
+            integrity: $integrity,
+            content: content!($path),
+        }
+    };
+}
+
+/// Third-party assets. `vendor/fetch.sh` and `build.rs` read this list, keep one asset per line
+#[rustfmt::skip]
+pub const ASSETS: &[VendoredAsset] = &[
+    vendored!("highlight.js/11.8.0/highlight.min.js", None),
+    vendored!("highlight.js/11.8.0/styles/github-dark.min.css", None),
+    vendored!("highlight.js/11.8.0/styles/github.min.css", None),
+    vendored!("highlight.js/11.8.0/styles/base16/ocean.min.css", None),
+    vendored!("highlight.js/11.8.0/styles/base16/eighties.min.css", None),
+    vendored!("highlight.js/11.8.0/styles/base16/mocha.min.css", None),
+    vendored!("highlight.js/11.8.0/styles/base16/solarized-dark.min.css", None),
+    vendored!("highlight.js/11.8.0/styles/base16/solarized-light.min.css", None),
+];
+
+impl VendoredAsset {
+    /// Find a bundled asset by its path
+    pub fn find(path: &str) -> Option<&'static VendoredAsset> {
+        ASSETS.iter().find(|asset| asset.path == path)
+    }
+
+    /// Path in output directory, relative to output root
+    pub fn output_path(&self) -> PathBuf {
+        Path::new("vendor").join(self.path)
+    }
+}
+
+/// Link to a third-party asset and its pinned integrity hash, if it's loaded from [CDN].
+/// Local assets are linked from output directory, if they are bundled
+pub fn link(project_root: &Path, path: &str, local: bool) -> (String, Option<String>) {
+    let asset = VendoredAsset::find(path);
+    match asset {
+        Some(asset) if local && AVAILABLE => (
+            project_root
+                .join(asset.output_path())
+                .to_string_lossy()
+                .into_owned(),
+            None,
+        ),
+        _ => (
+            format!("{CDN}/{path}"),
+            asset.and_then(|asset| asset.integrity.map(str::to_owned)),
+        ),
+    }
+}
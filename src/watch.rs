@@ -0,0 +1,97 @@
+use anyhow::Context;
+use notify::{EventKind, RecursiveMode, Watcher};
+use oreneo::site::{Site, SUMMARY_FILE};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Time to wait for more events after the first one, so that a single save doesn't trigger multiple rebuilds
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+fn report(result: Result<(), oreneo::site::SiteError>) {
+    if let Err(err) = result {
+        eprintln!("Error: {:?}", anyhow::Error::from(err));
+    }
+}
+
+/// Build the whole site, printing errors
+pub fn build_all(site: &Site) {
+    report(site.build().map(|_| ()));
+}
+
+/// Rebuild pages affected by changed files. Paths are relative to page directory
+pub fn rebuild(site: &Site, changed: &BTreeSet<PathBuf>) {
+    let mut pages = BTreeSet::new();
+    for path in changed {
+        let dependents = site.dependents(path);
+        if !site.page_root().join(path).exists() && dependents.contains(path) {
+            println!("Removed {path:?}");
+            report(site.remove_page(path));
+        }
+        pages.extend(dependents);
+    }
+
+    if site.refresh_navigation() {
+        println!("Navigation changed, rebuilding all pages");
+        build_all(site);
+        return;
+    }
+
+    let rebuild_indexes = !pages.is_empty() || changed.contains(Path::new(SUMMARY_FILE));
+    for page_path in pages {
+        if site.page_root().join(&page_path).is_file() {
+            println!("Rebuilding {page_path:?}");
+            match site.build_page(&page_path) {
+                Ok(Some(_)) => (),
+                Ok(None) => println!("Excluded {page_path:?}, it's a draft or scheduled"),
+                Err(err) => report(Err(err)),
+            }
+        }
+    }
+    if rebuild_indexes {
+        report(site.write_indexes());
+    }
+}
+
+/// Build the site, then watch page directory and rebuild pages when they change.
+/// Errors are printed, but never stop watching. `on_rebuild` is called after each rebuild
+pub fn watch(site: &Site, mut on_rebuild: impl FnMut()) -> anyhow::Result<()> {
+    build_all(site);
+
+    let page_root = site
+        .page_root()
+        .canonicalize()
+        .context("Failed to find page dir")?;
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).context("Failed to create file watcher")?;
+    watcher
+        .watch(&page_root, RecursiveMode::Recursive)
+        .context("Failed to watch page dir")?;
+    println!("Watching {:?} for changes...", site.page_root());
+
+    let relative = |path: &Path| path.strip_prefix(&page_root).ok().map(Path::to_owned);
+    while let Ok(event) = rx.recv() {
+        let mut changed = BTreeSet::new();
+        let mut collect = |event: notify::Result<notify::Event>| match event {
+            Ok(event) => {
+                if !matches!(event.kind, EventKind::Access(_)) {
+                    changed.extend(event.paths.iter().filter_map(|path| relative(path)));
+                }
+            }
+            Err(err) => eprintln!("Watch error: {err}"),
+        };
+
+        collect(event);
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+            collect(event);
+        }
+
+        if !changed.is_empty() {
+            rebuild(site, &changed);
+            on_rebuild();
+        }
+    }
+
+    Ok(())
+}